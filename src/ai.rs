@@ -0,0 +1,161 @@
+use bevy::prelude::*;
+
+use crate::{
+    camera::CameraState,
+    character::{Char, Dir},
+    combat::{Attack, EnemyAttack},
+    enemy::EnemyAssets,
+    level::LevelEntity,
+    plugin::add_stages,
+    replay::GameRng,
+    screen::GameState,
+    timestep::{FixedTime, Interpolated, STEP, STEP_STAGES},
+};
+
+#[derive(Debug)]
+pub struct RandomAttack {
+    pub attack_index: u32,
+    pub timer: Timer,
+}
+
+pub fn random_attack_system(
+    commands: &mut Commands,
+    game_state: Res<GameState>,
+    clock: Res<FixedTime>,
+    enemy_assets: Res<EnemyAssets>,
+    mut game_rng: ResMut<GameRng>,
+    mut query: Query<(&mut RandomAttack, &Transform)>,
+) {
+    if !game_state.playing() || !clock.stepping() {
+        return;
+    }
+
+    use rand::Rng;
+
+    let rng = &mut game_rng.rng;
+
+    for (mut attack, transform) in query.iter_mut() {
+        attack.timer.tick(STEP);
+        if attack.timer.finished {
+            if rng.gen_range(0.0..1.0) > 0.6 {
+                for i in 0..8 {
+                    let pi = 2.0 * std::f32::consts::PI / 8.0 * i as f32;
+                    let speed = 100.0;
+
+                    commands
+                        .spawn(SpriteSheetBundle {
+                            sprite: TextureAtlasSprite::new(attack.attack_index),
+                            texture_atlas: enemy_assets.attack_atlas_handle.clone(),
+                            transform: Transform::from_translation(transform.translation),
+                            ..Default::default()
+                        })
+                        .with(Attack {
+                            velocity: Vec2::new(speed * pi.sin(), speed * pi.cos()),
+                        })
+                        .with(EnemyAttack)
+                        .with(Interpolated::default())
+                        .with(LevelEntity);
+                }
+            }
+        }
+    }
+}
+
+/// Walks back and forth between `left` and `right`.
+#[derive(Debug)]
+pub struct Patrol {
+    pub left: f32,
+    pub right: f32,
+    pub speed: f32,
+}
+
+pub fn patrol_system(
+    game_state: Res<GameState>,
+    clock: Res<FixedTime>,
+    camera_state: Res<CameraState>,
+    mut query: Query<(&mut Char, &Patrol, &Transform)>,
+) {
+    if !game_state.playing() || !clock.stepping() {
+        return;
+    }
+
+    for (mut ch, patrol, transform) in query.iter_mut() {
+        if !camera_state.in_scope(&transform.translation) {
+            ch.velocity.x = 0.0;
+            continue;
+        }
+
+        let x = transform.translation.x;
+        if x <= patrol.left {
+            ch.velocity.x = patrol.speed;
+        } else if x + ch.size.x >= patrol.right {
+            ch.velocity.x = -patrol.speed;
+        } else if ch.velocity.x == 0.0 {
+            // stopped by a wall; turn around
+            ch.velocity.x = if ch.dir == Dir::Right {
+                -patrol.speed
+            } else {
+                patrol.speed
+            };
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RandomWalk {
+    pub timer: Timer,
+    pub move_possibility: f32,
+    pub jump_possibility: f32,
+}
+
+pub fn random_walk_system(
+    game_state: Res<GameState>,
+    clock: Res<FixedTime>,
+    camera_state: Res<CameraState>,
+    mut game_rng: ResMut<GameRng>,
+    mut query: Query<(&mut Char, &mut RandomWalk, &mut Transform)>,
+) {
+    if !game_state.playing() || !clock.stepping() {
+        return;
+    }
+
+    use rand::Rng;
+
+    let rng = &mut game_rng.rng;
+
+    for (mut ch, mut walk, transform) in query.iter_mut() {
+        if !camera_state.in_scope(&transform.translation) {
+            ch.velocity.x = 0.0;
+            continue;
+        }
+        walk.timer.tick(STEP);
+        if walk.timer.finished {
+            if walk.jump_possibility > rng.gen_range(0.0..1.0) {
+                ch.velocity.y = 200.0;
+            }
+            if walk.move_possibility > rng.gen_range(0.0..1.0) {
+                ch.velocity.x = rng.gen_range(-100.0..100.0);
+            } else {
+                ch.velocity.x = 0.0;
+            }
+        }
+    }
+}
+
+/// Movement and attacks of the enemies.
+pub struct AiPlugin;
+
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_stages(app);
+        app.init_resource::<GameRng>()
+            .init_resource::<CameraState>()
+            .init_resource::<EnemyAssets>();
+
+        for &[_, decide, _, _] in STEP_STAGES.iter() {
+            app.add_system_to_stage(decide, random_walk_system)
+                .add_system_to_stage(decide, patrol_system)
+                .add_system_to_stage(decide, random_attack_system);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::{prelude::*, render::camera::Camera, render::camera::OrthographicProjection};
+
+use crate::{
+    character::Player,
+    plugin::add_stages,
+    timestep::{interpolation_snapshot_system, interpolation_system},
+};
+
+#[derive(Debug, Default)]
+pub struct CameraState {
+    pub transform: Transform,
+    pub projection: OrthographicProjection,
+}
+
+impl CameraState {
+    pub fn scope(&self) -> (f32, f32, f32, f32) {
+        let min_x =
+            self.transform.translation.x + self.projection.left * self.transform.scale.x * 1.5;
+        let min_y =
+            self.transform.translation.y + self.projection.bottom * self.transform.scale.y * 1.5;
+        let max_x =
+            self.transform.translation.x + self.projection.right * self.transform.scale.x * 1.5;
+        let max_y =
+            self.transform.translation.y + self.projection.top * self.transform.scale.y * 1.5;
+        (min_x, min_y, max_x, max_y)
+    }
+
+    /// Camera view extended by `margin` on every side.
+    pub fn view(&self, margin: f32) -> Rect<f32> {
+        let translation = self.transform.translation;
+        let scale = self.transform.scale;
+        Rect {
+            left: translation.x + self.projection.left * scale.x - margin,
+            right: translation.x + self.projection.right * scale.x + margin,
+            bottom: translation.y + self.projection.bottom * scale.y - margin,
+            top: translation.y + self.projection.top * scale.y + margin,
+        }
+    }
+
+    pub fn in_scope(&self, translation: &Vec3) -> bool {
+        let x = translation.x;
+        let y = translation.y;
+        let (min_x, min_y, max_x, max_y) = self.scope();
+        return min_x <= x && x <= max_x && min_y <= y && y <= max_y;
+    }
+}
+
+pub fn camera_system(
+    query: Query<(&Player, &Transform)>,
+    mut camera_state: ResMut<CameraState>,
+    mut camera: Query<(&mut Camera, &OrthographicProjection, &mut Transform)>,
+) {
+    for (_, player_transform) in query.iter() {
+        for (_, projection, mut camera_transform) in camera.iter_mut() {
+            camera_transform.translation = player_transform.translation.clone();
+            camera_transform.scale = Vec3::splat(0.3);
+
+            camera_state.transform = camera_transform.clone();
+            camera_state.projection = projection.clone();
+        }
+    }
+}
+
+/// Camera following the player, and drawing between simulation steps.
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_stages(app);
+        app.init_resource::<CameraState>()
+            .add_system_to_stage("snapshot", interpolation_snapshot_system)
+            .add_system_to_stage("camera", camera_system)
+            .add_system_to_stage("interpolate", interpolation_system);
+    }
+}
@@ -0,0 +1,165 @@
+use bevy::prelude::*;
+use derive_new::new;
+use std::collections::HashMap;
+
+use crate::{
+    atlas::AtlasBuilder, config::GameConfig, controls::CharMotion, physics::Gravity,
+    screen::GameState, timestep::Interpolated,
+};
+
+pub struct Player {
+    pub life: u32,
+    max_life: u32,
+    pub(crate) attack_atlas_handle: Handle<TextureAtlas>,
+    pub(crate) attack_timer: Timer,
+    pub(crate) hazard_timer: Timer,
+}
+
+impl Player {
+    pub(crate) fn new(attack_atlas_handle: Handle<TextureAtlas>, life: u32) -> Self {
+        Self {
+            life,
+            max_life: life,
+            attack_atlas_handle,
+            attack_timer: Timer::from_seconds(0.2, false),
+            hazard_timer: Timer::from_seconds(0.5, false),
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.life = self.max_life;
+        self.attack_timer.reset();
+        self.hazard_timer.reset();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dir {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum State {
+    Jump,
+    Run,
+    Stop,
+}
+
+#[derive(Debug)]
+pub struct Char {
+    pub dir: Dir,
+    /// Direction the sprite faces unflipped.
+    init_dir: Dir,
+    pub state: State,
+    pub velocity: Vec3,
+    pub size: Vec2,
+    pub on_ground: bool,
+    pub on_hazard: bool,
+    pub drop_through: bool,
+}
+
+impl Char {
+    pub(crate) fn new(init_dir: Dir, size: Vec2) -> Self {
+        Self {
+            dir: Dir::Right,
+            init_dir,
+            state: State::Stop,
+            velocity: Vec3::zero(),
+            size,
+            on_ground: false,
+            on_hazard: false,
+            drop_through: false,
+        }
+    }
+
+    pub(crate) fn flip(&mut self, transform: &mut Transform) {
+        if self.dir != self.init_dir {
+            transform.rotation = Quat::from_rotation_y(std::f32::consts::PI);
+        } else {
+            transform.rotation = Quat::default();
+        }
+    }
+}
+
+#[derive(Debug, new)]
+pub struct Animate {
+    animation: HashMap<State, Vec<u32>>,
+    #[new(default)]
+    index: usize,
+}
+
+impl Animate {
+    pub fn next(&mut self, state: State) -> u32 {
+        let animation = self.animation.get(&state).unwrap();
+        let index = animation[self.index % animation.len()];
+        self.index = (self.index + 1) % animation.len();
+        index
+    }
+}
+
+pub fn setup_player(
+    commands: &mut Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let atlas_handle = AtlasBuilder::load(
+        &asset_server,
+        Vec2::new(32.0, 32.0),
+        Vec2::new(160.0, 32.0),
+        &config.player_texture,
+    )
+    .padding(Vec2::new(0.0, 0.0))
+    .scale(Vec2::splat(1.0 / 2.0))
+    .build(&mut atlases);
+
+    let attack_atlas_handle = AtlasBuilder::load(
+        &asset_server,
+        Vec2::new(32.0, 32.0),
+        Vec2::new(32.0, 32.0),
+        &config.attack_texture,
+    )
+    .padding(Vec2::new(0.0, 0.0))
+    .scale(Vec2::splat(0.5))
+    .build(&mut atlases);
+
+    let mut animate_map = HashMap::new();
+    animate_map.insert(State::Stop, vec![0]);
+    animate_map.insert(State::Run, (1..5).collect());
+    animate_map.insert(State::Jump, vec![0]);
+
+    commands
+        .spawn(Camera2dBundle::default())
+        .with(Interpolated::default())
+        .spawn(SpriteSheetBundle {
+            sprite: TextureAtlasSprite::new(8),
+            texture_atlas: atlas_handle,
+            transform: Transform::from_translation(Vec3::new(1000.0, -500.0, 0.0)),
+            ..Default::default()
+        })
+        .with(Player::new(attack_atlas_handle, config.player_life))
+        .with(CharMotion::default())
+        .with(Timer::from_seconds(0.2, true))
+        .with(Char::new(Dir::Right, Vec2::new(16.0, 16.0)))
+        .with(Gravity)
+        .with(Animate::new(animate_map))
+        .with(Interpolated::default());
+}
+
+pub fn animate_system(
+    game_state: Res<GameState>,
+    time: Res<Time>,
+    mut query: Query<(&Char, &mut Animate, &mut Timer, &mut TextureAtlasSprite)>,
+) {
+    if !game_state.playing() {
+        return;
+    }
+
+    for (ch, mut animate, mut timer, mut sprite) in query.iter_mut() {
+        timer.tick(time.delta_seconds);
+        if timer.finished {
+            sprite.index = animate.next(ch.state);
+        }
+    }
+}
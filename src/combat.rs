@@ -0,0 +1,225 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use crate::{
+    camera::CameraState,
+    character::{Char, Dir, Player},
+    config::GameConfig,
+    controls::CharMotion,
+    enemy::{Boss, Enemy},
+    level::{LevelEntity, LevelState},
+    plugin::add_stages,
+    score::{combo_system, Score},
+    screen::{GameState, Screen},
+    stats::{run_time_system, RunStats},
+    timestep::{FixedTime, Interpolated, STEP, STEP_STAGES},
+};
+
+#[derive(Debug)]
+pub struct Attack {
+    pub velocity: Vec2,
+}
+
+#[derive(Debug)]
+pub struct EnemyAttack;
+
+#[derive(Debug)]
+pub struct PlayerAttack;
+
+pub fn shoot_system(
+    commands: &mut Commands,
+    game_state: Res<GameState>,
+    config: Res<GameConfig>,
+    clock: Res<FixedTime>,
+    mut stats: ResMut<RunStats>,
+    mut query: Query<(&mut Player, &Char, &CharMotion, &Transform)>,
+) {
+    if !game_state.playing() || !clock.stepping() {
+        return;
+    }
+
+    for (mut player, ch, state, transform) in query.iter_mut() {
+        player.attack_timer.tick(STEP);
+        if !player.attack_timer.finished || !state.attack {
+            continue;
+        }
+        player.attack_timer.reset();
+        stats.attacks_fired += 1;
+
+        let x = if ch.dir == Dir::Right {
+            config.attack_speed
+        } else {
+            -config.attack_speed
+        };
+
+        commands
+            .spawn(SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(0),
+                texture_atlas: player.attack_atlas_handle.clone(),
+                transform: Transform::from_translation(transform.translation),
+                ..Default::default()
+            })
+            .with(Attack {
+                velocity: Vec2::new(x, 0.0),
+            })
+            .with(PlayerAttack)
+            .with(Interpolated::default())
+            .with(LevelEntity);
+    }
+}
+
+pub fn attack_move_system(
+    game_state: Res<GameState>,
+    clock: Res<FixedTime>,
+    mut query: Query<(&Attack, &mut Transform)>,
+) {
+    if !game_state.playing() || !clock.stepping() {
+        return;
+    }
+
+    for (attack, mut transform) in query.iter_mut() {
+        transform.translation.x += STEP * attack.velocity.x;
+        transform.translation.y += STEP * attack.velocity.y;
+    }
+}
+
+pub fn attack_collision_system(
+    commands: &mut Commands,
+    clock: Res<FixedTime>,
+    mut game_state: ResMut<GameState>,
+    mut level_state: ResMut<LevelState>,
+    mut stats: ResMut<RunStats>,
+    mut score: ResMut<Score>,
+    camera_state: Res<CameraState>,
+    mut players: Query<(&mut Player, &Char, &Transform)>,
+    mut enemies: Query<(Entity, &mut Enemy, &Char, &Transform, Option<&Boss>)>,
+    enemy_attacks: Query<(Entity, &EnemyAttack, &Transform)>,
+    player_attacks: Query<(Entity, &PlayerAttack, &Transform)>,
+) {
+    if !game_state.playing() || !clock.stepping() {
+        return;
+    }
+
+    for (mut player, ch, transform) in players.iter_mut() {
+        let mut damage = 0;
+
+        player.hazard_timer.tick(STEP);
+        if ch.on_hazard && player.hazard_timer.finished {
+            player.hazard_timer.reset();
+            damage += 1;
+        }
+
+        for (e, _, attack_transform) in enemy_attacks.iter() {
+            let min_x = transform.translation.x;
+            let min_y = transform.translation.y;
+            let max_x = transform.translation.x + ch.size.x;
+            let max_y = transform.translation.y + ch.size.y;
+
+            let amin_x = attack_transform.translation.x;
+            let amin_y = attack_transform.translation.y;
+            let amax_x = amin_x + 16.0;
+            let amax_y = amin_y + 16.0;
+
+            if max_x < amin_x || amax_x < min_x || max_y < amin_y || amax_y < min_y {
+                continue;
+            }
+
+            damage += 1;
+            commands.despawn(e);
+        }
+
+        if damage > 0 {
+            stats.damage_taken += damage;
+            player.life = player.life.saturating_sub(damage);
+            if player.life == 0 {
+                if level_state.checkpoint.is_some() {
+                    level_state.respawn_pending = true;
+                } else {
+                    game_state.screen = Screen::GameOver;
+                }
+            }
+        }
+    }
+
+    // an attack is spent on the first enemy it hits
+    let mut spent = HashSet::new();
+
+    for (ee, mut enemy, ch, transform, boss) in enemies.iter_mut() {
+        if !camera_state.in_scope(&transform.translation) {
+            continue;
+        }
+
+        for (e, _, attack_transform) in player_attacks.iter() {
+            if spent.contains(&e) {
+                continue;
+            }
+
+            let min_x = transform.translation.x;
+            let min_y = transform.translation.y;
+            let max_x = transform.translation.x + ch.size.x;
+            let max_y = transform.translation.y + ch.size.y;
+
+            let amin_x = attack_transform.translation.x;
+            let amin_y = attack_transform.translation.y;
+            let amax_x = amin_x + 16.0;
+            let amax_y = amin_y + 16.0;
+
+            if max_x < amin_x || amax_x < min_x || max_y < amin_y || amax_y < min_y {
+                continue;
+            }
+
+            spent.insert(e);
+            stats.attacks_hit += 1;
+            enemy.life -= 1;
+            if enemy.life == 0 {
+                stats.enemies_defeated += 1;
+                level_state.defeated.insert(enemy.index);
+                let points = score.defeat(enemy.lgtm, boss.is_some());
+                debug!("Defeated for {} points", points);
+                commands.despawn(ee);
+            }
+
+            commands.despawn(e);
+        }
+    }
+}
+
+pub fn cleanup_attack_system(
+    commands: &mut Commands,
+    camera_state: Res<CameraState>,
+    attacks: Query<(Entity, &Attack, &Transform)>,
+) {
+    let (min_x, min_y, max_x, max_y) = camera_state.scope();
+
+    for (e, _, transform) in attacks.iter() {
+        let x = transform.translation.x;
+        let y = transform.translation.y;
+        if x >= min_x && x < max_x && y >= min_y && y < max_y {
+            continue;
+        }
+        commands.despawn(e);
+    }
+}
+
+/// Attacks, damage, score and run statistics.
+pub struct CombatPlugin;
+
+impl Plugin for CombatPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_stages(app);
+        app.init_resource::<GameConfig>()
+            .init_resource::<LevelState>()
+            .init_resource::<CameraState>()
+            .init_resource::<RunStats>()
+            .init_resource::<Score>()
+            .add_system(cleanup_attack_system)
+            .add_system(run_time_system);
+
+        for &[_, decide, moves, hit] in STEP_STAGES.iter() {
+            app.add_system_to_stage(decide, shoot_system)
+                .add_system_to_stage(moves, attack_move_system)
+                .add_system_to_stage(hit, attack_collision_system)
+                .add_system_to_stage(hit, combo_system);
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Tunables and assets of the game, for adjusting it or making themed versions of it.
+
+/// Insert it before adding `GamePlugin` to override the defaults.
+#[derive(Debug, Clone)]
+pub struct GameConfig {
+    /// Shown on the title screen.
+    pub title: String,
+    pub player_life: u32,
+    /// Downward acceleration of characters, in pixels per second squared.
+    pub gravity: f32,
+    /// Horizontal speed of the player, in pixels per second.
+    pub walk_speed: f32,
+    /// Upward speed of the player as a jump starts, in pixels per second.
+    pub jump_speed: f32,
+    /// Speed of the attacks of the player, in pixels per second.
+    pub attack_speed: f32,
+    pub font: String,
+    pub player_texture: String,
+    pub attack_texture: String,
+    pub life_texture: String,
+    pub enemy_texture: String,
+    /// Attack icons of the enemies, one per enemy of the list in a row.
+    pub enemy_attack_texture: String,
+    pub tile_texture: String,
+    pub enemy_list: String,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            title: "Yubaba 2D platformer".to_string(),
+            player_life: 30,
+            gravity: 588.0,
+            walk_speed: 100.0,
+            jump_speed: 300.0,
+            attack_speed: 500.0,
+            font: "fonts/DejaVuSans.ttf".to_string(),
+            player_texture: "textures/char.png".to_string(),
+            attack_texture: "textures/attack.png".to_string(),
+            life_texture: "textures/life.png".to_string(),
+            enemy_texture: "textures/enemy.png".to_string(),
+            enemy_attack_texture: "textures/enemies_sheet.png".to_string(),
+            tile_texture: "textures/tiles.png".to_string(),
+            enemy_list: "data/enemies.enemylist".to_string(),
+        }
+    }
+}
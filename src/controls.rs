@@ -0,0 +1,97 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::{Char, Player},
+    config::GameConfig,
+    gamepad::{gamepad_connection_system, Gamepads},
+    keybinds::{Action, KeyBinds},
+    plugin::add_stages,
+    replay::{replay_system, GameRng, ReplayState},
+    screen::GameState,
+    timestep::{FixedTime, STEP_STAGES},
+    touch::{touch_input_system, TouchControls},
+};
+
+#[derive(Debug, Default)]
+pub struct CharMotion {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub attack: bool,
+}
+
+pub fn track_inputs_system(
+    keys: Res<Input<KeyCode>>,
+    binds: Res<KeyBinds>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    touch: Res<TouchControls>,
+    mut query: Query<(&Player, &mut CharMotion)>,
+) {
+    let pressed = |action| {
+        binds.pressed(action, &keys)
+            || gamepads.pressed(action, &buttons, &axes)
+            || touch.pressed(action)
+    };
+
+    for (_, mut motion) in query.iter_mut() {
+        motion.up = pressed(Action::Up);
+        motion.down = pressed(Action::Down);
+        motion.left = pressed(Action::Left);
+        motion.right = pressed(Action::Right);
+        motion.attack = pressed(Action::Attack);
+    }
+}
+
+pub fn move_char_system(
+    game_state: Res<GameState>,
+    config: Res<GameConfig>,
+    clock: Res<FixedTime>,
+    mut query: Query<(&mut Char, &Player, &CharMotion)>,
+) {
+    if !game_state.playing() || !clock.stepping() {
+        return;
+    }
+
+    for (mut ch, _, state) in query.iter_mut() {
+        if state.up && ch.on_ground {
+            ch.velocity.y = config.jump_speed;
+            ch.on_ground = false;
+        }
+
+        ch.drop_through = state.down;
+
+        if state.right {
+            ch.velocity.x = config.walk_speed;
+        } else if state.left {
+            ch.velocity.x = -config.walk_speed;
+        } else {
+            ch.velocity.x = 0.0;
+        }
+    }
+}
+
+/// Keyboard, gamepad and touch input, and replays.
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_stages(app);
+        app.init_resource::<GameConfig>()
+            .init_resource::<KeyBinds>()
+            .init_resource::<ReplayState>()
+            .init_resource::<Gamepads>()
+            .init_resource::<TouchControls>()
+            .init_resource::<GameRng>()
+            .add_system_to_stage(stage::PRE_UPDATE, gamepad_connection_system)
+            .add_system_to_stage(stage::PRE_UPDATE, touch_input_system)
+            .add_system_to_stage(stage::PRE_UPDATE, track_inputs_system)
+            .add_system_to_stage("replay", replay_system);
+
+        for &[_, decide, _, _] in STEP_STAGES.iter() {
+            app.add_system_to_stage(decide, move_char_system);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use bevy::{prelude::*, type_registry::TypeUuid};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ai::{Patrol, RandomAttack, RandomWalk},
+    atlas::AtlasBuilder,
+    character::{Animate, Char, Dir, State},
+    config::GameConfig,
+    level::LevelEntity,
+    physics::Gravity,
+    terrain::{EnemyAi, MapObject, ObjectKind},
+    timestep::Interpolated,
+};
+
+#[derive(Debug)]
+pub struct Enemy {
+    /// Index in the enemy list of the level, which tells apart enemies of the same user.
+    pub index: usize,
+    pub user: String,
+    pub life: u32,
+    pub lgtm: u32,
+}
+
+#[derive(Debug)]
+pub struct Boss;
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct EnemyInfo {
+    user: String,
+    lgtm: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, TypeUuid)]
+#[uuid = "36709c47-5df9-426b-bd64-8923b0fbdad3"]
+pub struct EnemyList {
+    pub enemies: Vec<EnemyInfo>,
+}
+
+#[derive(Debug, Default)]
+pub struct EnemyAssets {
+    pub list: Handle<EnemyList>,
+    atlas_handle: Handle<TextureAtlas>,
+    pub attack_atlas_handle: Handle<TextureAtlas>,
+    animation: HashMap<State, Vec<u32>>,
+}
+
+pub fn setup_enemies(
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    mut enemy_assets: ResMut<EnemyAssets>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let atlas_handle = AtlasBuilder::load(
+        &asset_server,
+        Vec2::new(32.0, 32.0),
+        Vec2::new(160.0, 32.0),
+        &config.enemy_texture,
+    )
+    .padding(Vec2::new(0.0, 0.0))
+    .scale(Vec2::splat(1.0))
+    .build(&mut atlases);
+
+    let mut animate_map = HashMap::new();
+    animate_map.insert(State::Stop, vec![0]);
+    animate_map.insert(State::Run, (1..5).collect());
+    animate_map.insert(State::Jump, vec![1, 3]);
+
+    *enemy_assets = EnemyAssets {
+        list: asset_server.load(config.enemy_list.as_str()),
+        atlas_handle,
+        attack_atlas_handle: Handle::default(),
+        animation: animate_map,
+    };
+}
+
+/// Rebuilds the attack atlas whenever the enemy list is (re)loaded, as it has one icon per enemy.
+pub fn enemy_assets_system(
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    mut reader: Local<EventReader<AssetEvent<EnemyList>>>,
+    events: Res<Events<AssetEvent<EnemyList>>>,
+    enemy_lists: Res<Assets<EnemyList>>,
+    mut enemy_assets: ResMut<EnemyAssets>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    for event in reader.iter(&events) {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+
+        let enemies = match enemy_lists.get(handle) {
+            Some(enemies) => enemies,
+            None => continue,
+        };
+
+        enemy_assets.attack_atlas_handle = AtlasBuilder::load(
+            &asset_server,
+            Vec2::new(32.0, 32.0),
+            Vec2::new(32.0 * enemies.enemies.len() as f32, 32.0),
+            &config.enemy_attack_texture,
+        )
+        .padding(Vec2::new(0.0, 0.0))
+        .scale(Vec2::splat(0.4))
+        .build(&mut atlases);
+    }
+}
+
+/// Spawns `enemies`, given with their index in the enemy list, at the `spawns` markers in turn.
+///
+/// Maps without enemy markers fall back to placing enemies on a grid; on other maps, enemies
+/// beyond the markers are left out. Enemies whose index in the level is `defeated` keep their
+/// marker empty.
+/// Returns the number of bosses spawned.
+pub fn spawn_enemies<'a>(
+    commands: &mut Commands,
+    assets: &EnemyAssets,
+    enemies: impl Iterator<Item = (usize, &'a EnemyInfo)>,
+    spawns: &[&MapObject],
+    defeated: &HashSet<usize>,
+) -> usize {
+    let mut bosses = 0;
+    let mut left_out = 0;
+
+    for (n, (i, e)) in enemies.enumerate() {
+        if !spawns.is_empty() && n >= spawns.len() {
+            left_out += 1;
+            continue;
+        }
+        if defeated.contains(&n) {
+            continue;
+        }
+
+        let spawn = spawns.get(n);
+        let (position, ai, patrol, boss) = match spawn.map(|s| (s.position(), &s.kind)) {
+            Some((position, ObjectKind::Enemy { ai, patrol, boss })) => {
+                (position, *ai, *patrol, *boss)
+            }
+            _ => {
+                let px = n % 16;
+                let py = n / 16;
+                let position =
+                    Vec3::new(250.0 * px as f32 + 100.0, -500.0 - 180.0 * py as f32, 0.0);
+                (position, EnemyAi::Wander, None, false)
+            }
+        };
+
+        let scale = if boss { 2.0 } else { 1.0 };
+        let mut transform = Transform::from_translation(position);
+        transform.scale = Vec3::splat(scale);
+        // sprites are drawn centred, so move the scaled up ones back over their box
+        let offset = Vec3::new(16.0, 16.0, 0.0) * (scale - 1.0);
+
+        commands
+            .spawn(SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(15),
+                texture_atlas: assets.atlas_handle.clone(),
+                transform,
+                ..Default::default()
+            })
+            .with(Timer::from_seconds(0.2, true))
+            .with(Enemy {
+                index: n,
+                user: e.user.clone(),
+                life: if boss { (e.lgtm + 1) * 5 } else { e.lgtm + 1 },
+                lgtm: e.lgtm,
+            })
+            .with(Char::new(Dir::Left, Vec2::new(32.0, 32.0) * scale))
+            .with(Animate::new(assets.animation.clone()))
+            .with(RandomAttack {
+                timer: Timer::from_seconds(1.0, true),
+                attack_index: i as u32,
+            })
+            .with(Gravity)
+            .with(Interpolated::offset(offset))
+            .with(LevelEntity);
+
+        match (ai, patrol) {
+            (EnemyAi::Wander, _) => {
+                commands.with(RandomWalk {
+                    timer: Timer::from_seconds(1.0, true),
+                    move_possibility: 0.3,
+                    jump_possibility: 0.3,
+                });
+            }
+            (EnemyAi::Patrol, Some((left, right))) => {
+                commands.with(Patrol {
+                    left,
+                    right,
+                    speed: 60.0,
+                });
+            }
+            (EnemyAi::Patrol, None) => {
+                commands.with(Patrol {
+                    left: position.x - 80.0,
+                    right: position.x + 80.0,
+                    speed: 60.0,
+                });
+            }
+            (EnemyAi::Guard, _) => {}
+        }
+
+        if boss {
+            commands.with(Boss);
+            bosses += 1;
+        }
+    }
+
+    if left_out > 0 {
+        warn!("{} enemies left out for lack of enemy markers", left_out);
+    }
+
+    bosses
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::keybinds::Action;
+
+/// Stick deflection below this is ignored, so worn sticks don't drift the character.
+const STICK_DEAD_ZONE: f32 = 0.4;
+
+/// Connected gamepads, kept up to date as pads are plugged in and out.
+#[derive(Debug, Default)]
+pub struct Gamepads {
+    pads: Vec<Gamepad>,
+}
+
+impl Gamepads {
+    fn stick(&self, axes: &Axis<GamepadAxis>, axis: GamepadAxisType) -> f32 {
+        self.pads
+            .iter()
+            .filter_map(|&pad| axes.get(GamepadAxis(pad, axis)))
+            .find(|v| v.abs() > STICK_DEAD_ZONE)
+            .unwrap_or(0.0)
+    }
+
+    fn button(&self, buttons: &Input<GamepadButton>, button: GamepadButtonType) -> bool {
+        self.pads
+            .iter()
+            .any(|&pad| buttons.pressed(GamepadButton(pad, button)))
+    }
+
+    pub fn just_pressed(&self, buttons: &Input<GamepadButton>, button: GamepadButtonType) -> bool {
+        self.pads
+            .iter()
+            .any(|&pad| buttons.just_pressed(GamepadButton(pad, button)))
+    }
+
+    /// Whether any pad holds `action`: d-pad or left stick to move, south to jump, west or east
+    /// to attack.
+    pub fn pressed(
+        &self,
+        action: Action,
+        buttons: &Input<GamepadButton>,
+        axes: &Axis<GamepadAxis>,
+    ) -> bool {
+        match action {
+            Action::Up => self.button(buttons, GamepadButtonType::South),
+            Action::Down => {
+                self.button(buttons, GamepadButtonType::DPadDown)
+                    || self.stick(axes, GamepadAxisType::LeftStickY) < 0.0
+            }
+            Action::Left => {
+                self.button(buttons, GamepadButtonType::DPadLeft)
+                    || self.stick(axes, GamepadAxisType::LeftStickX) < 0.0
+            }
+            Action::Right => {
+                self.button(buttons, GamepadButtonType::DPadRight)
+                    || self.stick(axes, GamepadAxisType::LeftStickX) > 0.0
+            }
+            Action::Attack => {
+                self.button(buttons, GamepadButtonType::West)
+                    || self.button(buttons, GamepadButtonType::East)
+            }
+        }
+    }
+}
+
+pub fn gamepad_connection_system(
+    mut reader: Local<EventReader<GamepadEvent>>,
+    events: Res<Events<GamepadEvent>>,
+    mut gamepads: ResMut<Gamepads>,
+) {
+    for GamepadEvent(pad, event) in reader.iter(&events) {
+        match event {
+            GamepadEventType::Connected => {
+                info!("Gamepad connected: {:?}", pad);
+                if !gamepads.pads.contains(pad) {
+                    gamepads.pads.push(*pad);
+                }
+            }
+            GamepadEventType::Disconnected => {
+                info!("Gamepad disconnected: {:?}", pad);
+                gamepads.pads.retain(|p| p != pad);
+            }
+            _ => {}
+        }
+    }
+}
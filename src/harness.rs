@@ -0,0 +1,247 @@
+//! Headless game for automated tests: the simulation without a window, renderer or assets, with
+//! scripted key presses, manual frame stepping and direct access to the entities.
+
+use bevy::{
+    core::CorePlugin,
+    ecs::{Component, Resource},
+    input::{keyboard::KeyboardInput, ElementState, InputPlugin},
+    prelude::*,
+    render::camera::OrthographicProjection,
+    window::WindowPlugin,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{
+    character::{Char, Dir, Player},
+    config::GameConfig,
+    controls::CharMotion,
+    enemy::Enemy,
+    level::{level_goal_system, Area, LevelInfo, LevelManifest, LevelState},
+    physics::Gravity,
+    plugin::SimulationPlugin,
+    screen::{GameState, Screen},
+    stats::RunStats,
+    terrain::{ShapeKind, TileGrid, TileLayer, TileShape},
+    timestep::STEP,
+};
+
+/// Length of every frame, in seconds, in place of the wall clock.
+struct FrameTime(f32);
+
+fn frame_time_system(frame_time: Res<FrameTime>, mut time: ResMut<Time>) {
+    time.delta = Duration::from_secs_f32(frame_time.0);
+    time.delta_seconds = frame_time.0;
+    time.delta_seconds_f64 = frame_time.0 as f64;
+}
+
+pub struct Harness {
+    pub app: App,
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Harness {
+    /// Game already playing its only level, on an empty map without exits, with frames of one
+    /// simulation step.
+    pub fn new() -> Self {
+        let mut builder = App::build();
+        builder
+            .add_plugin(CorePlugin::default())
+            .add_plugin(WindowPlugin::default())
+            .add_plugin(InputPlugin::default())
+            .add_resource(FrameTime(STEP))
+            // runs after the core plugin updates the clock
+            .add_system_to_stage(stage::FIRST, frame_time_system)
+            .add_resource(GameState {
+                screen: Screen::Playing,
+            })
+            .add_resource(LevelState {
+                next: None,
+                ..Default::default()
+            })
+            .add_resource(LevelManifest {
+                levels: vec![LevelInfo::default()],
+            })
+            .add_plugin(SimulationPlugin)
+            .add_system_to_stage(stage::POST_UPDATE, level_goal_system);
+
+        let mut app = builder.app;
+        app.world.spawn(Camera2dBundle {
+            orthographic_projection: OrthographicProjection {
+                left: -500.0,
+                right: 500.0,
+                bottom: -500.0,
+                top: 500.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        Self { app }
+    }
+
+    /// Sets the length of the following frames, in seconds.
+    pub fn frame_time(&mut self, seconds: f32) {
+        self.resource(|frame_time: &mut FrameTime| frame_time.0 = seconds);
+    }
+
+    /// Replaces the terrain with `rows` of tiles from the top of the map: `#` solid, `=` one-way
+    /// platform, `^` hazard, `/` and `\` 45° slopes rising to the right and to the left, and
+    /// anything else empty. Tile (x, y) covers `x * 16..x * 16 + 16` horizontally and
+    /// `-y * 16..-y * 16 + 16` vertically in world coordinates.
+    pub fn map(&mut self, rows: &[&str]) {
+        let map = rows
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        '#' => 1,
+                        '=' => 2,
+                        '^' => 3,
+                        '/' => 4,
+                        '\\' => 5,
+                        _ => 0,
+                    })
+                    .collect()
+            })
+            .collect();
+        let layer = TileLayer {
+            solid: true,
+            map,
+            ..Default::default()
+        };
+
+        let mut shapes = HashMap::new();
+        let kinds = [
+            (2, ShapeKind::OneWay, None),
+            (3, ShapeKind::Hazard, None),
+            (4, ShapeKind::Slope, Some((0.0, 16.0))),
+            (5, ShapeKind::Slope, Some((16.0, 0.0))),
+        ];
+        for &(id, kind, slope) in kinds.iter() {
+            let shape = TileShape {
+                kind,
+                slope,
+                ..Default::default()
+            };
+            shapes.insert(id, shape);
+        }
+
+        let grid = TileGrid::new(&[layer], &shapes);
+        self.resource(|g: &mut TileGrid| *g = grid);
+    }
+
+    /// Spawns the player with its bottom-left corner at `position`.
+    pub fn spawn_player(&mut self, position: Vec3) -> Entity {
+        let life = self.resource(|config: &mut GameConfig| config.player_life);
+        self.app.world.spawn((
+            Player::new(Handle::default(), life),
+            CharMotion::default(),
+            Char::new(Dir::Right, Vec2::new(16.0, 16.0)),
+            Gravity,
+            Transform::from_translation(position),
+            GlobalTransform::default(),
+        ))
+    }
+
+    /// Spawns an enemy that stands still, with its bottom-left corner at `position`. Enemies are
+    /// numbered in the level in the order they are spawned, and all belong to the same user.
+    pub fn spawn_enemy(&mut self, position: Vec3, life: u32) -> Entity {
+        let index = self.count::<Enemy>();
+        self.app.world.spawn((
+            Enemy {
+                index,
+                user: "enemy".to_string(),
+                life,
+                lgtm: 0,
+            },
+            Char::new(Dir::Left, Vec2::new(32.0, 32.0)),
+            Gravity,
+            Transform::from_translation(position),
+            GlobalTransform::default(),
+        ))
+    }
+
+    /// Holds `key` down from the next frame.
+    pub fn press(&mut self, key: KeyCode) {
+        self.key(key, ElementState::Pressed);
+    }
+
+    /// Lets go of `key` from the next frame.
+    pub fn release(&mut self, key: KeyCode) {
+        self.key(key, ElementState::Released);
+    }
+
+    fn key(&mut self, key: KeyCode, state: ElementState) {
+        self.resource(|events: &mut Events<KeyboardInput>| {
+            events.send(KeyboardInput {
+                scan_code: 0,
+                key_code: Some(key),
+                state,
+            })
+        });
+    }
+
+    /// Advances the game by `frames` frames.
+    pub fn run(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.app.update();
+        }
+    }
+
+    /// Calls `f` with the resource `T`.
+    pub fn resource<T: Resource, R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut resource = self.app.resources.get_mut::<T>().expect("no such resource");
+        f(&mut *resource)
+    }
+
+    /// Calls `f` with the component `T` of `entity`.
+    pub fn get<T: Component, R>(&self, entity: Entity, f: impl FnOnce(&T) -> R) -> R {
+        let component = self.app.world.get::<T>(entity).expect("no such component");
+        f(&*component)
+    }
+
+    /// Indices of the enemies defeated in the level, in order.
+    pub fn defeated(&mut self) -> Vec<usize> {
+        let mut defeated: Vec<_> =
+            self.resource(|state: &mut LevelState| state.defeated.iter().copied().collect());
+        defeated.sort();
+        defeated
+    }
+
+    /// Adds an exit to the level, with its bottom-left corner at `position`.
+    pub fn exit(&mut self, position: Vec3, size: Vec2) {
+        self.resource(|manifest: &mut LevelManifest| {
+            manifest.levels[0].exits.push(Area {
+                x: position.x,
+                y: position.y,
+                width: size.x,
+                height: size.y,
+            })
+        });
+    }
+
+    /// The level has been completed.
+    pub fn level_complete(&mut self) -> bool {
+        self.resource(|state: &mut LevelState| state.complete)
+    }
+
+    /// Attacks the player has fired in the run.
+    pub fn attacks_fired(&mut self) -> u32 {
+        self.resource(|stats: &mut RunStats| stats.attacks_fired)
+    }
+
+    pub fn translation(&self, entity: Entity) -> Vec3 {
+        self.get(entity, |transform: &Transform| transform.translation)
+    }
+
+    /// Number of entities with the component `T`.
+    pub fn count<T: Component>(&self) -> usize {
+        self.app.world.query::<&T>().count()
+    }
+}
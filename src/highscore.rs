@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    character::Player,
+    score::Score,
+    screen::{GameState, Screen},
+    stats::{time_text, RunStats},
+    storage,
+};
+
+const STORAGE_KEY: &str = "mcp2_highscores";
+
+/// Version of the stored table. Bump it and extend `migrate` when the format changes.
+const VERSION: u32 = 1;
+
+const MAX_ENTRIES: usize = 10;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct HighScore {
+    pub score: u32,
+    /// Play time in seconds.
+    pub time: f32,
+    pub enemies_defeated: u32,
+    /// Life left at the end of the run.
+    pub life: u32,
+    /// Start of the run in seconds since the unix epoch; identifies the run.
+    pub date: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HighScores {
+    pub version: u32,
+    pub entries: Vec<HighScore>,
+}
+
+impl Default for HighScores {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        let data = match storage::load(STORAGE_KEY) {
+            Some(data) => data,
+            None => return Self::new(),
+        };
+
+        match serde_json::from_str(&data).map(migrate) {
+            Ok(Some(scores)) => scores,
+            Ok(None) => {
+                warn!("Discarding high scores of an unknown version");
+                Self::new()
+            }
+            Err(e) => {
+                warn!("Couldn't read high scores: {}", e);
+                Self::new()
+            }
+        }
+    }
+
+    fn new() -> Self {
+        Self {
+            version: VERSION,
+            entries: vec![],
+        }
+    }
+
+    fn save(&self) {
+        let data = serde_json::to_string(self).unwrap();
+        if let Err(e) = storage::save(STORAGE_KEY, &data) {
+            warn!("Couldn't save high scores: {}", e);
+        }
+    }
+
+    /// Adds or updates the entry of a run, keeping the best ones.
+    pub fn record(&mut self, entry: HighScore) {
+        self.entries.retain(|e| e.date != entry.date);
+        self.entries.push(entry);
+        self.entries.sort_by(|a, b| b.score.cmp(&a.score));
+        self.entries.truncate(MAX_ENTRIES);
+    }
+
+    /// Table lines of the top `n` runs, empty without any run.
+    pub fn lines(&self, n: usize) -> Vec<String> {
+        if self.entries.is_empty() {
+            return vec![];
+        }
+
+        let mut lines = vec![String::new(), "High scores".to_string()];
+        lines.extend(self.entries.iter().take(n).enumerate().map(|(i, e)| {
+            format!(
+                "{}. {}  {}  {}",
+                i + 1,
+                e.score,
+                time_text(e.time),
+                storage::date_text(e.date)
+            )
+        }));
+        lines
+    }
+}
+
+/// Converts a stored table to the current format.
+fn migrate(value: serde_json::Value) -> Option<HighScores> {
+    match value.get("version")?.as_u64()? as u32 {
+        VERSION => serde_json::from_value(value).ok(),
+        _ => None,
+    }
+}
+
+/// Records the run whenever it ends, so a continued run updates its entry.
+pub fn high_score_system(
+    game_state: Res<GameState>,
+    stats: Res<RunStats>,
+    score: Res<Score>,
+    mut high_scores: ResMut<HighScores>,
+    mut last_screen: Local<Option<Screen>>,
+    players: Query<&Player>,
+) {
+    if *last_screen == Some(game_state.screen) {
+        return;
+    }
+    *last_screen = Some(game_state.screen);
+
+    if game_state.screen != Screen::GameOver && game_state.screen != Screen::Victory {
+        return;
+    }
+
+    high_scores.record(HighScore {
+        score: score.points,
+        time: stats.time,
+        enemies_defeated: stats.enemies_defeated,
+        life: players.iter().map(|p| p.life).next().unwrap_or(0),
+        date: stats.started,
+    });
+    high_scores.save();
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+
+use crate::{
+    atlas::AtlasBuilder,
+    camera::CameraState,
+    character::Player,
+    config::GameConfig,
+    gamepad::Gamepads,
+    highscore::{high_score_system, HighScores},
+    keybinds::{KeyBinds, RebindState},
+    level::LevelState,
+    plugin::add_stages,
+    replay::ReplayState,
+    save::SaveState,
+    score::{score_text_system, setup_score, Score},
+    screen::{screen_input_system, screen_overlay_system, setup_screen},
+    stats::RunStats,
+    timestep::Interpolated,
+    touch::{setup_touch, touch_visibility_system, TouchControls},
+};
+
+#[derive(Debug)]
+pub struct Life {
+    index: u32,
+}
+
+pub fn setup_life(
+    commands: &mut Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let life_atlas_handle = AtlasBuilder::load(
+        &asset_server,
+        Vec2::new(32.0, 32.0),
+        Vec2::new(64.0, 32.0),
+        &config.life_texture,
+    )
+    .padding(Vec2::new(0.0, 0.0))
+    .scale(Vec2::splat(1.0 / 4.0))
+    .build(&mut atlases);
+
+    for i in 0..config.player_life {
+        commands
+            .spawn(SpriteSheetBundle {
+                sprite: TextureAtlasSprite::new(1),
+                texture_atlas: life_atlas_handle.clone(),
+                ..Default::default()
+            })
+            .with(Life { index: i })
+            .with(Interpolated::default());
+    }
+}
+
+pub fn show_life_system(
+    camera_state: Res<CameraState>,
+    player: Query<&Player>,
+    mut query: Query<(&Life, &mut TextureAtlasSprite, &mut Transform)>,
+) {
+    let mut player_opt = None;
+    for p in player.iter() {
+        player_opt = Some(p);
+    }
+
+    for (life, mut sprite, mut transform) in query.iter_mut() {
+        sprite.index = if player_opt.map(|p| life.index < p.life).unwrap_or(false) {
+            1
+        } else {
+            0
+        };
+        transform.translation = camera_state.transform.translation;
+        transform.translation.x += camera_state.projection.left * camera_state.transform.scale.x
+            + 32.0
+            + life.index as f32 * 8.0;
+        transform.translation.y +=
+            camera_state.projection.top * camera_state.transform.scale.y - 64.0;
+        transform.translation.z = 100.0;
+    }
+}
+
+/// Screens, score, life and touch buttons over the game, and the high scores.
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_stages(app);
+        app.init_resource::<GameConfig>()
+            .init_resource::<LevelState>()
+            .init_resource::<RunStats>()
+            .init_resource::<Score>()
+            .init_resource::<SaveState>()
+            .init_resource::<HighScores>()
+            .init_resource::<KeyBinds>()
+            .init_resource::<ReplayState>()
+            .init_resource::<RebindState>()
+            .init_resource::<Gamepads>()
+            .init_resource::<TouchControls>()
+            .init_resource::<CameraState>()
+            .add_startup_system(setup_life)
+            .add_startup_system(setup_screen)
+            .add_startup_system(setup_score)
+            .add_startup_system(setup_touch)
+            .add_system_to_stage(stage::PRE_UPDATE, screen_input_system)
+            // placed against the camera of this frame
+            .add_system_to_stage(stage::POST_UPDATE, show_life_system)
+            .add_system(screen_overlay_system)
+            .add_system(touch_visibility_system)
+            .add_system(score_text_system)
+            .add_system(high_score_system);
+    }
+}
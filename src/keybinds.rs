@@ -0,0 +1,259 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::storage;
+
+const STORAGE_KEY: &str = "mcp2_keybinds";
+
+/// Keys that can be bound to actions. Keys driving the menus, like `P`, `R`, `C`, `K`, `V` and
+/// `I`, are left out.
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::J,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::Q,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::LShift,
+    KeyCode::RShift,
+    KeyCode::LControl,
+    KeyCode::RControl,
+    KeyCode::LAlt,
+    KeyCode::RAlt,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+];
+
+/// Most keys bound to one action.
+const MAX_KEYS: usize = 3;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Attack,
+}
+
+impl Action {
+    pub const ALL: [Action; 5] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::Attack,
+    ];
+}
+
+fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.iter().copied().find(|&k| key_name(k) == name)
+}
+
+/// Stored form of the bindings, with keys by name.
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct KeyConfig {
+    keys: HashMap<Action, Vec<String>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct KeyBinds {
+    keys: HashMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for KeyBinds {
+    fn default() -> Self {
+        Self::from_config(serde_json::from_slice(include_bytes!("keybinds.json")).unwrap())
+    }
+}
+
+impl KeyBinds {
+    /// Loads the bindings chosen by the user, or the default ones.
+    pub fn load() -> Self {
+        let defaults = Self::default();
+
+        let config = match storage::load(STORAGE_KEY) {
+            Some(data) => data,
+            None => return defaults,
+        };
+
+        match serde_json::from_str(&config) {
+            Ok(config) => {
+                let mut binds = Self::from_config(config);
+                // actions added since the bindings were saved
+                for (action, keys) in defaults.keys {
+                    binds.keys.entry(action).or_insert(keys);
+                }
+                binds
+            }
+            Err(e) => {
+                warn!("Couldn't read key bindings: {}", e);
+                defaults
+            }
+        }
+    }
+
+    fn from_config(config: KeyConfig) -> Self {
+        let keys = config
+            .keys
+            .into_iter()
+            .map(|(action, names)| {
+                let keys = names
+                    .iter()
+                    .filter_map(|name| {
+                        let key = parse_key(name);
+                        if key.is_none() {
+                            warn!("Unknown key `{}` for {:?}", name, action);
+                        }
+                        key
+                    })
+                    .collect();
+                (action, keys)
+            })
+            .collect();
+        Self { keys }
+    }
+
+    pub fn save(&self) {
+        let config = KeyConfig {
+            keys: self
+                .keys
+                .iter()
+                .map(|(&action, keys)| (action, keys.iter().map(|&k| key_name(k)).collect()))
+                .collect(),
+        };
+
+        if let Err(e) = storage::save(STORAGE_KEY, &serde_json::to_string(&config).unwrap()) {
+            warn!("Couldn't save key bindings: {}", e);
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.keys.get(&action).map(|k| k.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn pressed(&self, action: Action, input: &Input<KeyCode>) -> bool {
+        self.keys(action).iter().any(|&k| input.pressed(k))
+    }
+
+    /// Binds `key` to `action`, taking it from any other action.
+    fn bind(&mut self, action: Action, key: KeyCode) {
+        for keys in self.keys.values_mut() {
+            keys.retain(|&k| k != key);
+        }
+
+        let keys = self.keys.entry(action).or_default();
+        if keys.len() >= MAX_KEYS {
+            keys.remove(0);
+        }
+        keys.push(key);
+    }
+
+    fn clear(&mut self, action: Action) {
+        self.keys.entry(action).or_default().clear();
+    }
+}
+
+/// Cursor of the rebinding menu.
+#[derive(Debug, Default)]
+pub struct RebindState {
+    pub cursor: usize,
+    /// Waiting for the key to bind to the selected action.
+    pub waiting: bool,
+}
+
+impl RebindState {
+    /// Handles menu input; returns `true` when the menu is closed.
+    pub fn input(&mut self, keys: &Input<KeyCode>, binds: &mut KeyBinds) -> bool {
+        let action = Action::ALL[self.cursor];
+
+        if self.waiting {
+            if keys.just_pressed(KeyCode::Escape) {
+                self.waiting = false;
+            } else if let Some(&key) = BINDABLE_KEYS.iter().find(|&&k| keys.just_pressed(k)) {
+                binds.bind(action, key);
+                self.waiting = false;
+            }
+            return false;
+        }
+
+        if keys.just_pressed(KeyCode::Escape) {
+            binds.save();
+            return true;
+        }
+
+        if keys.just_pressed(KeyCode::Up) {
+            self.cursor = (self.cursor + Action::ALL.len() - 1) % Action::ALL.len();
+        } else if keys.just_pressed(KeyCode::Down) {
+            self.cursor = (self.cursor + 1) % Action::ALL.len();
+        } else if keys.just_pressed(KeyCode::Return) {
+            self.waiting = true;
+        } else if keys.just_pressed(KeyCode::Back) {
+            binds.clear(action);
+        }
+
+        false
+    }
+
+    pub fn lines(&self, binds: &KeyBinds) -> Vec<String> {
+        let mut lines = vec!["Key bindings".to_string()];
+
+        for (i, &action) in Action::ALL.iter().enumerate() {
+            let keys = if self.waiting && i == self.cursor {
+                "press a key...".to_string()
+            } else {
+                binds
+                    .keys(action)
+                    .iter()
+                    .map(|&k| key_name(k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            let marker = if i == self.cursor { ">" } else { " " };
+            lines.push(format!("{} {:?}: {}", marker, action, keys));
+        }
+
+        lines.push(String::new());
+        lines.push("Up/Down to select, Enter to add a key".to_string());
+        lines.push("Backspace to clear, Esc to go back".to_string());
+        lines
+    }
+}
@@ -0,0 +1,446 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::screen::{GameState, Screen};
+use crate::{
+    character::{animate_system, setup_player, Char, Player},
+    combat::Attack,
+    config::GameConfig,
+    enemy::{
+        enemy_assets_system, setup_enemies, spawn_enemies, Boss, Enemy, EnemyAssets, EnemyList,
+    },
+    loader::EnemyListLoader,
+    physics::to_rect,
+    plugin::{add_stages, add_tile_maps},
+    save::{restore_system, save_system, SaveState},
+    score::Score,
+    stats::RunStats,
+    terrain::{ChunkSettings, TileGrid, TileInfo, TileMap},
+};
+
+/// Axis-aligned area in world coordinates, `x` and `y` being the bottom-left corner.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub struct Area {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Area {
+    fn overlaps(&self, rect: &Rect<f32>) -> bool {
+        !(rect.right <= self.x
+            || self.x + self.width <= rect.left
+            || rect.top <= self.y
+            || self.y + self.height <= rect.bottom)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct LevelInfo {
+    pub name: String,
+    /// Asset path of the tile map of the level.
+    pub map: String,
+    /// Player spawn point, overriding the spawn marker of the map.
+    #[serde(default)]
+    pub spawn: Option<(f32, f32)>,
+    /// Range of the enemy list placed in the level.
+    pub enemies: (usize, usize),
+    /// Areas that complete the level when the player reaches them.
+    #[serde(default)]
+    pub exits: Vec<Area>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct LevelManifest {
+    pub levels: Vec<LevelInfo>,
+}
+
+pub fn load_manifest() -> LevelManifest {
+    serde_json::from_slice(include_bytes!("levels.json")).unwrap()
+}
+
+#[derive(Debug)]
+pub struct LevelState {
+    /// Index of the level being played.
+    pub current: usize,
+    /// Level to load once the transition timer finishes.
+    pub next: Option<usize>,
+    pub timer: Timer,
+    pub complete: bool,
+    /// Where the player comes back in the current level.
+    pub respawn: Vec3,
+    /// Brings the player back to `respawn` with full life.
+    pub respawn_pending: bool,
+    /// Last checkpoint touched in the current level.
+    pub checkpoint: Option<usize>,
+    /// Number of bosses in the current level; defeating them all completes it.
+    pub bosses: usize,
+    /// Indices in the level's enemy list of the enemies defeated in the current level, which stay
+    /// defeated on reload.
+    pub defeated: HashSet<usize>,
+}
+
+impl Default for LevelState {
+    fn default() -> Self {
+        Self {
+            current: 0,
+            next: Some(0),
+            timer: Timer::default(),
+            complete: false,
+            respawn: Vec3::zero(),
+            respawn_pending: false,
+            checkpoint: None,
+            bosses: 0,
+            defeated: HashSet::new(),
+        }
+    }
+}
+
+impl LevelState {
+    /// Reloads the level `index` from scratch and respawns the player there.
+    pub fn restart(&mut self, index: usize) {
+        self.next = Some(index);
+        self.timer = Timer::default();
+        self.respawn_pending = true;
+        self.defeated.clear();
+    }
+}
+
+/// Tile maps of the levels, kept loaded for the whole game.
+#[derive(Debug, Default)]
+pub struct LevelAssets {
+    pub maps: Vec<Handle<TileMap>>,
+}
+
+pub fn setup_levels(
+    asset_server: Res<AssetServer>,
+    manifest: Res<LevelManifest>,
+    mut level_assets: ResMut<LevelAssets>,
+) {
+    level_assets.maps = manifest
+        .levels
+        .iter()
+        .map(|level| asset_server.load(level.map.as_str()))
+        .collect();
+}
+
+#[derive(Debug)]
+pub struct Checkpoint {
+    index: usize,
+    area: Area,
+}
+
+/// Marks entities that belong to the current level and are torn down on transition.
+#[derive(Debug)]
+pub struct LevelEntity;
+
+pub fn level_load_system(
+    commands: &mut Commands,
+    time: Res<Time>,
+    manifest: Res<LevelManifest>,
+    level_assets: Res<LevelAssets>,
+    tilemaps: Res<Assets<TileMap>>,
+    enemy_lists: Res<Assets<EnemyList>>,
+    enemy_assets: Res<EnemyAssets>,
+    settings: Res<ChunkSettings>,
+    mut state: ResMut<LevelState>,
+    mut tileinfo: ResMut<TileInfo>,
+    mut grid: ResMut<TileGrid>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    entities: Query<(Entity, &LevelEntity)>,
+    mut players: Query<(&Player, &mut Char, &mut Transform)>,
+) {
+    let next = match state.next {
+        Some(next) => next,
+        None => return,
+    };
+
+    state.timer.tick(time.delta_seconds);
+    if !state.timer.finished {
+        return;
+    }
+
+    let level = match manifest.levels.get(next) {
+        Some(level) => level,
+        None => {
+            warn!("No such level: {}", next);
+            state.next = None;
+            return;
+        }
+    };
+
+    // wait for the assets of the level
+    let tilemap = match level_assets.maps.get(next).and_then(|h| tilemaps.get(h)) {
+        Some(tilemap) => tilemap,
+        None => return,
+    };
+    let enemy_list = match enemy_lists.get(&enemy_assets.list) {
+        Some(enemy_list) => enemy_list,
+        None => return,
+    };
+
+    for (entity, _) in entities.iter() {
+        commands.despawn(entity);
+    }
+
+    tileinfo.load(tilemap, settings.size, &mut materials);
+    *grid = TileGrid::new(&tilemap.layers, &tilemap.shapes);
+
+    let (start, end) = level.enemies;
+    let bosses = spawn_enemies(
+        commands,
+        &enemy_assets,
+        enemy_list.enemies.iter().enumerate().take(end).skip(start),
+        &tilemap.enemy_spawns(),
+        &state.defeated,
+    );
+
+    // tiles are drawn centred on their physics origin, and so are exits and checkpoints
+    let exit_material = materials.add(Color::rgba(1.0, 0.9, 0.2, 0.5).into());
+    for exit in level.exits.iter() {
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite::new(Vec2::new(exit.width, exit.height)),
+                material: exit_material.clone(),
+                transform: Transform::from_translation(Vec3::new(
+                    exit.x + exit.width / 2.0 - 8.0,
+                    exit.y + exit.height / 2.0 - 8.0,
+                    -0.5,
+                )),
+                ..Default::default()
+            })
+            .with(LevelEntity);
+    }
+
+    for (index, object) in tilemap.checkpoints().enumerate() {
+        let position = object.position();
+        let area = Area {
+            x: position.x,
+            y: position.y,
+            width: object.width,
+            height: object.height,
+        };
+
+        commands
+            .spawn(SpriteBundle {
+                sprite: Sprite::new(Vec2::new(area.width, area.height)),
+                material: materials.add(Color::rgba(0.5, 0.5, 0.5, 0.8).into()),
+                transform: Transform::from_translation(Vec3::new(
+                    area.x + area.width / 2.0 - 8.0,
+                    area.y + area.height / 2.0 - 8.0,
+                    -0.5,
+                )),
+                ..Default::default()
+            })
+            .with(Checkpoint { index, area })
+            .with(LevelEntity);
+    }
+
+    let spawn = level
+        .spawn
+        .map(|(x, y)| Vec3::new(x, y, 0.0))
+        .or_else(|| tilemap.player_spawn());
+    match spawn {
+        Some(spawn) => {
+            for (_, mut ch, mut transform) in players.iter_mut() {
+                transform.translation = spawn;
+                ch.velocity = Vec3::zero();
+            }
+            state.respawn = spawn;
+        }
+        None => warn!("No player spawn in level: {}", level.name),
+    }
+
+    info!("Level {}: {}", next + 1, level.name);
+
+    state.current = next;
+    state.next = None;
+    state.complete = false;
+    state.checkpoint = None;
+    state.bosses = bosses;
+}
+
+pub fn level_goal_system(
+    manifest: Res<LevelManifest>,
+    mut game_state: ResMut<GameState>,
+    mut state: ResMut<LevelState>,
+    players: Query<(&Player, &Char, &Transform)>,
+    enemies: Query<&Enemy>,
+    bosses: Query<&Boss>,
+) {
+    if !game_state.playing() || state.complete || state.next.is_some() {
+        return;
+    }
+
+    let level = match manifest.levels.get(state.current) {
+        Some(level) => level,
+        None => return,
+    };
+
+    let defeated = level.enemies.0 < level.enemies.1 && enemies.iter().next().is_none();
+    let boss_defeated = state.bosses > 0 && bosses.iter().next().is_none();
+    let exited = players.iter().any(|(_, ch, transform)| {
+        let rect = to_rect(&transform.translation, &ch.size);
+        level.exits.iter().any(|exit| exit.overlaps(&rect))
+    });
+
+    if !defeated && !boss_defeated && !exited {
+        return;
+    }
+
+    info!("Level {} complete", state.current + 1);
+    state.complete = true;
+
+    if state.current + 1 < manifest.levels.len() {
+        state.next = Some(state.current + 1);
+        state.timer = Timer::from_seconds(1.5, false);
+        state.defeated.clear();
+    } else {
+        game_state.screen = Screen::Victory;
+    }
+}
+
+/// Reloads the current level when its tile map or the enemy list changes on disk.
+pub fn level_reload_system(
+    mut map_reader: Local<EventReader<AssetEvent<TileMap>>>,
+    map_events: Res<Events<AssetEvent<TileMap>>>,
+    mut enemy_reader: Local<EventReader<AssetEvent<EnemyList>>>,
+    enemy_events: Res<Events<AssetEvent<EnemyList>>>,
+    level_assets: Res<LevelAssets>,
+    mut state: ResMut<LevelState>,
+) {
+    let current = level_assets.maps.get(state.current);
+
+    let maps = map_reader
+        .iter(&map_events)
+        .filter(|e| matches!(e, AssetEvent::Modified { handle } if Some(handle) == current))
+        .count();
+    let enemies = enemy_reader
+        .iter(&enemy_events)
+        .filter(|e| matches!(e, AssetEvent::Modified { .. }))
+        .count();
+
+    if (maps > 0 || enemies > 0) && state.next.is_none() {
+        info!("Reloading level {}", state.current + 1);
+        state.next = Some(state.current);
+        state.timer = Timer::default();
+    }
+}
+
+pub fn level_respawn_system(
+    commands: &mut Commands,
+    mut state: ResMut<LevelState>,
+    attacks: Query<(Entity, &Attack)>,
+    mut players: Query<(&mut Player, &mut Char, &mut Transform)>,
+) {
+    if !state.respawn_pending || state.next.is_some() {
+        return;
+    }
+    state.respawn_pending = false;
+
+    for (entity, _) in attacks.iter() {
+        commands.despawn(entity);
+    }
+
+    for (mut player, mut ch, mut transform) in players.iter_mut() {
+        player.reset();
+        ch.velocity = Vec3::zero();
+        transform.translation = state.respawn;
+    }
+
+    info!("Respawned at {:?}", state.respawn);
+}
+
+/// Makes the last touched checkpoint the respawn point of the level.
+pub fn checkpoint_system(
+    game_state: Res<GameState>,
+    mut state: ResMut<LevelState>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    players: Query<(&Player, &Char, &Transform)>,
+    checkpoints: Query<(&Checkpoint, &Handle<ColorMaterial>)>,
+) {
+    if !game_state.playing() || state.next.is_some() {
+        return;
+    }
+
+    for (_, ch, transform) in players.iter() {
+        let rect = to_rect(&transform.translation, &ch.size);
+
+        for (checkpoint, _) in checkpoints.iter() {
+            if state.checkpoint == Some(checkpoint.index) || !checkpoint.area.overlaps(&rect) {
+                continue;
+            }
+
+            info!("Checkpoint {}", checkpoint.index + 1);
+            state.checkpoint = Some(checkpoint.index);
+            state.respawn = Vec3::new(checkpoint.area.x, checkpoint.area.y, 0.0);
+        }
+    }
+
+    // highlight the active checkpoint
+    for (checkpoint, material) in checkpoints.iter() {
+        let color = if state.checkpoint == Some(checkpoint.index) {
+            Color::rgba(0.2, 0.9, 0.3, 0.8)
+        } else {
+            Color::rgba(0.5, 0.5, 0.5, 0.8)
+        };
+
+        let changed = materials.get(material).map_or(false, |m| m.color != color);
+        if changed {
+            if let Some(material) = materials.get_mut(material) {
+                material.color = color;
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn setup_hot_reload(asset_server: Res<AssetServer>) {
+    if let Err(e) = asset_server.watch_for_changes() {
+        warn!("Couldn't watch the assets for changes: {}", e);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn setup_hot_reload() {}
+
+/// Levels, the player and the enemies in them, and saving the progress.
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if app.resources().get::<LevelManifest>().is_none() {
+            app.add_resource(load_manifest());
+        }
+
+        add_stages(app);
+        add_tile_maps(app);
+        app.add_asset::<EnemyList>()
+            .init_asset_loader::<EnemyListLoader>()
+            .init_resource::<GameConfig>()
+            .init_resource::<LevelState>()
+            .init_resource::<SaveState>()
+            .init_resource::<RunStats>()
+            .init_resource::<Score>()
+            .init_resource::<EnemyAssets>()
+            .init_resource::<TileGrid>()
+            .init_resource::<TileInfo>()
+            .init_resource::<ChunkSettings>()
+            .init_resource::<LevelAssets>()
+            .add_startup_system(setup_hot_reload)
+            .add_startup_system(setup_levels)
+            .add_startup_system(setup_enemies)
+            .add_startup_system(setup_player)
+            .add_system_to_stage("level", level_load_system)
+            .add_system_to_stage("level", level_respawn_system)
+            .add_system_to_stage("level", restore_system)
+            .add_system(animate_system)
+            .add_system(enemy_assets_system)
+            .add_system(level_reload_system)
+            .add_system(save_system)
+            .add_system_to_stage(stage::POST_UPDATE, level_goal_system)
+            .add_system_to_stage(stage::POST_UPDATE, checkpoint_system);
+    }
+}
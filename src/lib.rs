@@ -0,0 +1,83 @@
+use bevy::{asset::AssetServerSettings, prelude::*};
+
+mod ai;
+mod atlas;
+mod camera;
+mod character;
+mod combat;
+mod config;
+mod controls;
+mod enemy;
+mod gamepad;
+#[cfg(any(test, feature = "harness"))]
+pub mod harness;
+mod highscore;
+mod hud;
+mod keybinds;
+mod level;
+mod loader;
+mod physics;
+mod plugin;
+mod replay;
+mod save;
+mod score;
+mod screen;
+mod stats;
+mod storage;
+mod terrain;
+mod tilemesh;
+mod timestep;
+mod touch;
+
+pub use crate::ai::AiPlugin;
+pub use crate::camera::CameraPlugin;
+pub use crate::character::{Char, Dir, Player, State};
+pub use crate::combat::{Attack, CombatPlugin, EnemyAttack, PlayerAttack};
+pub use crate::config::GameConfig;
+pub use crate::controls::ControlsPlugin;
+pub use crate::enemy::Enemy;
+pub use crate::hud::HudPlugin;
+pub use crate::level::LevelPlugin;
+pub use crate::physics::PhysicsPlugin;
+pub use crate::plugin::{GamePlugin, SimulationPlugin};
+pub use crate::terrain::TerrainPlugin;
+
+/// Runs the game in the browser window, or in a desktop window on native builds.
+pub fn run() {
+    let config = GameConfig::default();
+    let mut app = App::build();
+
+    app.add_resource(WindowDescriptor {
+        title: config.title.clone(),
+        width: 1000,
+        height: 1000,
+        ..Default::default()
+    })
+    .add_resource(AssetServerSettings {
+        asset_folder: asset_folder(),
+    })
+    .add_resource(config);
+    add_default_plugins(&mut app);
+    app.add_plugin(GamePlugin).run();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn asset_folder() -> String {
+    option_env!("MCP2_PREFIX").unwrap_or("").to_string()
+}
+
+/// The assets are in `static`, or in the folder set by `MCP2_ASSETS` at runtime.
+#[cfg(not(target_arch = "wasm32"))]
+fn asset_folder() -> String {
+    std::env::var("MCP2_ASSETS").unwrap_or_else(|_| "static".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn add_default_plugins(app: &mut AppBuilder) {
+    app.add_plugins(bevy_webgl2::DefaultPlugins);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn add_default_plugins(app: &mut AppBuilder) {
+    app.add_plugins(DefaultPlugins);
+}
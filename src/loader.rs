@@ -0,0 +1,50 @@
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    utils::BoxedFuture,
+};
+
+use crate::{enemy::EnemyList, terrain::TileMap};
+
+/// Loads tile maps exported by the `tiles` tool.
+#[derive(Default)]
+pub struct TileMapLoader;
+
+impl AssetLoader for TileMapLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let tilemap: TileMap = serde_json::from_slice(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(tilemap));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tilemap"]
+    }
+}
+
+/// Loads enemy lists exported by the `fetch` tool.
+#[derive(Default)]
+pub struct EnemyListLoader;
+
+impl AssetLoader for EnemyListLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let enemies: EnemyList = serde_json::from_slice(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(enemies));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemylist"]
+    }
+}
@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+
+use crate::{
+    character::{Char, Dir, State},
+    config::GameConfig,
+    plugin::add_stages,
+    screen::GameState,
+    terrain::{ShapeKind, TileGrid},
+    timestep::{FixedTime, STEP, STEP_STAGES},
+};
+
+#[derive(Debug)]
+pub struct Gravity;
+
+pub fn to_rect(translation: &Vec3, size: &Vec2) -> Rect<f32> {
+    Rect {
+        left: translation.x,
+        right: translation.x + size.x,
+        bottom: translation.y,
+        top: translation.y + size.y,
+    }
+}
+
+/// Highest point of a slope surface under the horizontal span `left..right`.
+fn slope_ground(terrain: &Rect<f32>, slope: (f32, f32), left: f32, right: f32) -> Option<f32> {
+    let left = left.max(terrain.left);
+    let right = right.min(terrain.right);
+    if left >= right {
+        return None;
+    }
+
+    let width = terrain.right - terrain.left;
+    let surface =
+        |x: f32| terrain.bottom + slope.0 + (slope.1 - slope.0) * (x - terrain.left) / width;
+
+    Some(surface(left).max(surface(right)))
+}
+
+pub fn physics_system(
+    game_state: Res<GameState>,
+    config: Res<GameConfig>,
+    clock: Res<FixedTime>,
+    grid: Res<TileGrid>,
+    mut query: Query<(&mut Char, &mut Transform)>,
+) {
+    if !game_state.playing() || !clock.stepping() {
+        return;
+    }
+
+    for (mut ch, mut cht) in query.iter_mut() {
+        ch.velocity.y -= config.gravity * STEP;
+        step_char(&mut ch, &mut cht, &grid, STEP);
+    }
+}
+
+/// Moves a character by `dt` seconds of its velocity, resolving collisions with the terrain.
+fn step_char(ch: &mut Char, cht: &mut Transform, grid: &TileGrid, dt: f32) {
+    let old_ch = to_rect(&cht.translation, &ch.size);
+
+    let new_ch_pos = cht.translation + dt * ch.velocity;
+    let new_ch = to_rect(&new_ch_pos, &ch.size);
+
+    let mut possible_y = new_ch_pos.y;
+    let mut possible_x = new_ch_pos.x;
+    let mut new_velocity = ch.velocity.clone();
+
+    // how far a slope surface can rise or fall under the character in this step
+    let reach = ch.velocity.x.abs() * dt + 1.0;
+    let mut ground_y: Option<f32> = None;
+    let was_on_ground = ch.on_ground;
+
+    ch.on_ground = false;
+    ch.on_hazard = false;
+
+    let area = Rect {
+        left: old_ch.left.min(new_ch.left),
+        right: old_ch.right.max(new_ch.right),
+        bottom: old_ch.bottom.min(new_ch.bottom),
+        top: old_ch.top.max(new_ch.top),
+    };
+
+    let terrains = grid.around(&area).map(|(t, r)| (t.kind, t.slope, r));
+
+    for (kind, slope, terrain) in terrains {
+        if kind == ShapeKind::Slope {
+            // slopes keep characters on their surface instead of blocking them
+            if ch.velocity.y > 0.0 {
+                continue;
+            }
+
+            let slope = slope.unwrap_or((0.0, terrain.top - terrain.bottom));
+            let ground = match slope_ground(&terrain, slope, new_ch.left, new_ch.right) {
+                Some(ground) => ground,
+                None => continue,
+            };
+
+            // stick to the surface when walking downhill
+            let snap = if was_on_ground { reach } else { 0.0 };
+
+            if new_ch.bottom <= ground + snap && old_ch.bottom + reach >= ground {
+                ground_y = Some(ground_y.map_or(ground, |y| y.max(ground)));
+            }
+            continue;
+        }
+
+        if new_ch.right <= terrain.left
+            || terrain.right <= new_ch.left
+            || new_ch.top <= terrain.bottom
+            || terrain.top <= new_ch.bottom
+        {
+            // no collision
+            continue;
+        }
+
+        match kind {
+            ShapeKind::None => continue,
+            ShapeKind::Hazard => {
+                ch.on_hazard = true;
+                continue;
+            }
+            ShapeKind::Solid
+                if was_on_ground
+                    && ch.velocity.y <= 0.0
+                    && old_ch.bottom < terrain.top
+                    && terrain.top <= old_ch.bottom + reach =>
+            {
+                // step onto the tile at the top of a slope instead of running into its side
+                ch.on_ground = true;
+                possible_y = possible_y.max(terrain.top);
+                new_velocity.y = 0.0;
+                continue;
+            }
+            ShapeKind::OneWay => {
+                // one-way platforms only stop characters falling onto them from above
+                if ch.drop_through || ch.velocity.y > 0.0 || old_ch.bottom < terrain.top {
+                    continue;
+                }
+
+                ch.on_ground = true;
+                possible_y = possible_y.max(terrain.top);
+                new_velocity.y = 0.0;
+                continue;
+            }
+            _ => {}
+        }
+
+        // can collide; constraint character position
+
+        // time until top/bottom collision
+        let ty = if ch.velocity.y < 0.0 && terrain.top <= old_ch.bottom {
+            (terrain.top - old_ch.bottom) / ch.velocity.y
+        } else if ch.velocity.y > 0.0 && old_ch.top <= terrain.bottom {
+            (terrain.bottom - old_ch.top) / ch.velocity.y
+        } else {
+            f32::INFINITY
+        };
+
+        // time until left/right collision
+        let tx = if ch.velocity.x < 0.0 && terrain.right <= old_ch.left {
+            (terrain.right - old_ch.left) / ch.velocity.x
+        } else if ch.velocity.x > 0.0 && old_ch.right <= terrain.left {
+            (terrain.left - old_ch.right) / ch.velocity.x
+        } else {
+            f32::INFINITY
+        };
+
+        ch.on_ground = ty == 0.0;
+
+        if ty <= tx {
+            // top/bottom collides before left/right collides
+
+            if ch.velocity.y < 0.0 {
+                // character bottom collides
+                possible_y = possible_y.max(terrain.top);
+            } else {
+                // character top collides
+                possible_y = possible_y.min(terrain.bottom - ch.size.y);
+            }
+
+            new_velocity.y = 0.0;
+        } else {
+            // left/right collides before top/bottom collides
+
+            if ch.velocity.x < 0.0 {
+                // character left collides
+                possible_x = possible_x.max(terrain.right);
+            } else {
+                // character right collides
+                possible_x = possible_x.min(terrain.left - ch.size.x);
+            }
+
+            new_velocity.x = 0.0;
+        }
+    }
+
+    if let Some(ground) = ground_y {
+        possible_y = if new_velocity.y == 0.0 {
+            possible_y.max(ground)
+        } else {
+            ground
+        };
+        new_velocity.y = 0.0;
+        ch.on_ground = true;
+    }
+
+    cht.translation.x = possible_x;
+    cht.translation.y = possible_y;
+    ch.velocity = new_velocity;
+
+    if ch.velocity.x != 0.0 {
+        if ch.velocity.x > 0.0 {
+            ch.dir = Dir::Right;
+        } else {
+            ch.dir = Dir::Left;
+        }
+        ch.flip(cht);
+    }
+
+    if ch.velocity.y != 0.0 {
+        ch.state = State::Jump;
+    } else if ch.velocity.x != 0.0 {
+        ch.state = State::Run;
+    } else {
+        ch.state = State::Stop;
+    }
+}
+
+/// Gravity and collisions of the characters with the terrain.
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_stages(app);
+        app.init_resource::<GameConfig>()
+            .init_resource::<TileGrid>();
+
+        for &[_, _, moves, _] in STEP_STAGES.iter() {
+            app.add_system_to_stage(moves, physics_system);
+        }
+    }
+}
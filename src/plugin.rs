@@ -0,0 +1,107 @@
+//! The whole game, and the stages its plugins share. `GamePlugin` adds the plugin of every
+//! module; the others are for apps that only want part of the game, like the headless
+//! `SimulationPlugin` of the tests. Each one sets up the stages and resources its systems use, so
+//! any of them can be added on its own.
+
+use bevy::prelude::*;
+
+use crate::{
+    ai::AiPlugin,
+    camera::CameraPlugin,
+    combat::CombatPlugin,
+    controls::ControlsPlugin,
+    highscore::HighScores,
+    hud::HudPlugin,
+    keybinds::KeyBinds,
+    level::LevelPlugin,
+    loader::TileMapLoader,
+    physics::PhysicsPlugin,
+    replay::ReplayState,
+    save::SaveState,
+    screen::GameState,
+    terrain::{TerrainPlugin, TileMap},
+    timestep::{fixed_time_system, step_system, FixedTime, STEP_STAGES},
+};
+
+/// The whole game. Resources inserted before it, like `GameConfig`, take the place of the
+/// defaults and of what is loaded from the storage.
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if app.resources().get::<HighScores>().is_none() {
+            app.add_resource(HighScores::load());
+        }
+        if app.resources().get::<SaveState>().is_none() {
+            app.add_resource(SaveState::load());
+        }
+        if app.resources().get::<KeyBinds>().is_none() {
+            app.add_resource(KeyBinds::load());
+        }
+        if app.resources().get::<ReplayState>().is_none() {
+            app.add_resource(ReplayState::load());
+        }
+
+        app.add_plugin(SimulationPlugin)
+            .add_plugin(TerrainPlugin)
+            .add_plugin(LevelPlugin)
+            .add_plugin(HudPlugin);
+    }
+}
+
+/// Marks an app that has the stages of the game.
+struct GameStages;
+
+/// Adds the stages the systems of the game run in, and the fixed clock stepping through them,
+/// unless a plugin of the game already has, so every plugin can be added on its own.
+pub fn add_stages(app: &mut AppBuilder) {
+    if app.resources().get::<GameStages>().is_some() {
+        return;
+    }
+
+    app.add_resource(GameStages)
+        .init_resource::<GameState>()
+        .init_resource::<FixedTime>()
+        // the replay feeds in the input and frame time, which the levels and the clock then use
+        .add_stage_after(stage::PRE_UPDATE, "replay")
+        .add_stage_after("replay", "level")
+        .add_stage_after("level", "clock")
+        .add_system_to_stage("clock", fixed_time_system)
+        // positions are snapshot once the clock knows how many steps to run
+        .add_stage_after("clock", "snapshot");
+
+    let mut last = stage::UPDATE;
+    for stages in STEP_STAGES.iter() {
+        for &stage in stages.iter() {
+            app.add_stage_after(last, stage);
+            last = stage;
+        }
+        app.add_system_to_stage(stages[0], step_system);
+    }
+
+    // the camera follows the player once all steps have moved it
+    app.add_stage_after(last, "camera")
+        .add_stage_after(stage::POST_UPDATE, "interpolate");
+}
+
+/// Adds the tile map assets, which both the terrain and the levels use, unless already added.
+pub fn add_tile_maps(app: &mut AppBuilder) {
+    if app.resources().get::<Assets<TileMap>>().is_none() {
+        app.add_asset::<TileMap>()
+            .init_asset_loader::<TileMapLoader>();
+    }
+}
+
+/// Gameplay simulation: input, movement, physics, AI, combat and the camera. It needs no renderer
+/// or loaded assets, so it also runs in a headless app, given the core, window and input plugins.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_plugin(ControlsPlugin)
+            .add_plugin(PhysicsPlugin)
+            .add_plugin(AiPlugin)
+            .add_plugin(CombatPlugin)
+            .add_plugin(CameraPlugin);
+    }
+}
@@ -0,0 +1,294 @@
+//! Records the player input of a run, and replays it frame by frame with the same random seed.
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{closure::Closure, JsCast};
+
+use crate::{
+    character::Player,
+    controls::CharMotion,
+    screen::{GameState, Screen},
+    storage,
+    timestep::FixedTime,
+};
+
+const STORAGE_KEY: &str = "mcp2_replay";
+
+/// Version of the replay format; replays of other versions are ignored.
+const VERSION: u32 = 1;
+
+/// Random source of the gameplay, reseeded at the start of every recorded or replayed run.
+pub struct GameRng {
+    pub rng: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(rand::random()),
+        }
+    }
+}
+
+/// Frame time and input bits of a played frame.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ReplayFrame(pub f32, pub u8);
+
+/// Run from the start of the first level, up to the game over or clear.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Off,
+    Recording,
+    Playing,
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Off
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ReplayState {
+    mode: Mode,
+    replay: Replay,
+    /// Next frame to record or replay.
+    frame: usize,
+    /// Replay the title screen offers to watch: of the last finished run, or imported since.
+    pub saved: Option<Replay>,
+    /// Replay file read by `import`, waiting to take the place of the saved replay.
+    imported: Arc<Mutex<Option<String>>>,
+}
+
+impl ReplayState {
+    pub fn load() -> Self {
+        let saved = storage::load(STORAGE_KEY)
+            .and_then(|data| serde_json::from_str::<Replay>(&data).ok())
+            .filter(|replay| replay.version == VERSION);
+
+        Self {
+            saved,
+            ..Default::default()
+        }
+    }
+
+    /// Starts recording a new run.
+    pub fn record(&mut self) {
+        self.mode = Mode::Recording;
+        self.replay = Replay {
+            version: VERSION,
+            seed: rand::random(),
+            frames: vec![],
+        };
+        self.frame = 0;
+    }
+
+    /// Starts replaying the saved run, if any.
+    pub fn play(&mut self) -> bool {
+        match self.saved.clone() {
+            Some(replay) => {
+                self.mode = Mode::Playing;
+                self.replay = replay;
+                self.frame = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Imports a replay file: picked in the browser on wasm, or read from the path in
+    /// `MCP2_REPLAY` on native. It is taken in on a later frame.
+    pub fn import(&mut self) {
+        #[cfg(target_arch = "wasm32")]
+        {
+            if let Err(e) = pick_file(".json", self.imported.clone()) {
+                warn!("Couldn't import a replay: {}", e);
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let path = match std::env::var("MCP2_REPLAY") {
+                Ok(path) => path,
+                Err(_) => {
+                    warn!("Set MCP2_REPLAY to the path of the replay to import");
+                    return;
+                }
+            };
+            match std::fs::read_to_string(&path) {
+                Ok(data) => *self.imported.lock().unwrap() = Some(data),
+                Err(e) => warn!("Couldn't import a replay from {}: {}", path, e),
+            }
+        }
+    }
+
+    /// Makes the imported replay, if any, the one to watch. The storage keeps the replay of the
+    /// last recorded run.
+    fn receive(&mut self) {
+        let data = match self.imported.lock().unwrap().take() {
+            Some(data) => data,
+            None => return,
+        };
+
+        match serde_json::from_str::<Replay>(&data) {
+            Ok(replay) if replay.version == VERSION => {
+                info!("Imported a replay of {} frames", replay.frames.len());
+                self.saved = Some(replay);
+            }
+            Ok(replay) => warn!("Unsupported replay version: {}", replay.version),
+            Err(e) => warn!("Couldn't read the replay: {}", e),
+        }
+    }
+
+    pub fn playing(&self) -> bool {
+        self.mode == Mode::Playing
+    }
+
+    /// Drops the run being recorded or replayed.
+    pub fn stop(&mut self) {
+        self.mode = Mode::Off;
+    }
+
+    fn finish(&mut self) {
+        match self.mode {
+            Mode::Recording => {
+                info!("Recorded {} frames", self.replay.frames.len());
+                let replay = std::mem::take(&mut self.replay);
+                match serde_json::to_string(&replay) {
+                    Ok(data) => {
+                        if let Err(e) = storage::save(STORAGE_KEY, &data) {
+                            warn!("Failed to save the replay: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("Failed to save the replay: {}", e),
+                }
+                self.saved = Some(replay);
+            }
+            Mode::Playing => info!("Replay finished at frame {}", self.frame),
+            Mode::Off => {}
+        }
+        self.mode = Mode::Off;
+    }
+}
+
+/// Opens the browser's file picker, and puts the text of the picked file in `picked` once read.
+#[cfg(target_arch = "wasm32")]
+fn pick_file(accept: &str, picked: Arc<Mutex<Option<String>>>) -> Result<(), String> {
+    let input = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| "no document".to_string())?
+        .create_element("input")
+        .map_err(|e| format!("{:?}", e))?
+        .dyn_into::<web_sys::HtmlInputElement>()
+        .map_err(|e| format!("{:?}", e))?;
+    input.set_type("file");
+    input.set_accept(accept);
+
+    let files = input.clone();
+    let onchange = Closure::once_into_js(move || {
+        let file = match files.files().and_then(|files| files.get(0)) {
+            Some(file) => file,
+            None => return,
+        };
+        let reader = match web_sys::FileReader::new() {
+            Ok(reader) => reader,
+            Err(e) => {
+                warn!("Couldn't read the file: {:?}", e);
+                return;
+            }
+        };
+
+        let result = reader.clone();
+        let onload = Closure::once_into_js(move || {
+            match result.result().ok().and_then(|text| text.as_string()) {
+                Some(text) => *picked.lock().unwrap() = Some(text),
+                None => warn!("Couldn't read the file as text"),
+            }
+        });
+        reader.set_onload(Some(onload.unchecked_ref()));
+        if let Err(e) = reader.read_as_text(&file) {
+            warn!("Couldn't read the file: {:?}", e);
+        }
+    });
+    input.set_onchange(Some(onchange.unchecked_ref()));
+    input.click();
+
+    Ok(())
+}
+
+fn encode(motion: &CharMotion) -> u8 {
+    motion.up as u8
+        | (motion.down as u8) << 1
+        | (motion.left as u8) << 2
+        | (motion.right as u8) << 3
+        | (motion.attack as u8) << 4
+}
+
+fn decode(bits: u8, motion: &mut CharMotion) {
+    motion.up = bits & 1 != 0;
+    motion.down = bits & 1 << 1 != 0;
+    motion.left = bits & 1 << 2 != 0;
+    motion.right = bits & 1 << 3 != 0;
+    motion.attack = bits & 1 << 4 != 0;
+}
+
+/// Records the input of every played frame, or feeds the recorded input and frame time back in
+/// place of the live ones. Runs after the input is read and before anything consumes it, the
+/// level loading and the simulation clock included.
+pub fn replay_system(
+    game_state: Res<GameState>,
+    mut time: ResMut<Time>,
+    mut state: ResMut<ReplayState>,
+    mut game_rng: ResMut<GameRng>,
+    mut clock: ResMut<FixedTime>,
+    mut players: Query<(&Player, &mut CharMotion)>,
+) {
+    state.receive();
+
+    match game_state.screen {
+        Screen::GameOver | Screen::Victory => {
+            state.finish();
+            return;
+        }
+        Screen::Playing if state.mode != Mode::Off => {}
+        _ => return,
+    }
+
+    if state.frame == 0 {
+        game_rng.rng = StdRng::seed_from_u64(state.replay.seed);
+        *clock = FixedTime::default();
+    }
+
+    if state.mode == Mode::Recording {
+        let input = players.iter_mut().next().map_or(0, |(_, m)| encode(&m));
+        let delta = time.delta_seconds;
+        state.replay.frames.push(ReplayFrame(delta, input));
+    } else {
+        let ReplayFrame(delta, input) = match state.replay.frames.get(state.frame) {
+            Some(frame) => *frame,
+            None => {
+                state.finish();
+                return;
+            }
+        };
+
+        time.delta = Duration::from_secs_f32(delta);
+        time.delta_seconds = delta;
+        time.delta_seconds_f64 = delta as f64;
+        for (_, mut motion) in players.iter_mut() {
+            decode(input, &mut motion);
+        }
+    }
+
+    state.frame += 1;
+}
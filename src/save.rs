@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    character::{Char, Player},
+    level::LevelState,
+    score::Score,
+    screen::{GameState, Screen},
+    stats::RunStats,
+    storage,
+};
+
+const STORAGE_KEY: &str = "mcp2_save";
+
+/// Version of the save format; saves of other versions are ignored.
+const VERSION: u32 = 2;
+
+/// Snapshot of a run in progress.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SaveData {
+    pub version: u32,
+    pub level: usize,
+    pub position: (f32, f32),
+    pub life: u32,
+    /// Indices in the level's enemy list of the enemies defeated in the level.
+    pub defeated: Vec<usize>,
+    pub score: u32,
+    pub checkpoint: Option<usize>,
+    pub respawn: (f32, f32),
+    pub stats: RunStats,
+}
+
+#[derive(Debug, Default)]
+pub struct SaveState {
+    /// Last save, which the title screen offers to continue.
+    pub saved: Option<SaveData>,
+    /// Saves the run at the next opportunity.
+    pub pending: bool,
+    /// Save to apply once its level is loaded.
+    pub restore: Option<SaveData>,
+}
+
+impl SaveState {
+    pub fn load() -> Self {
+        let saved = storage::load(STORAGE_KEY)
+            .and_then(|data| serde_json::from_str::<SaveData>(&data).ok())
+            .filter(|save| save.version == VERSION);
+
+        Self {
+            saved,
+            ..Default::default()
+        }
+    }
+}
+
+/// Saves the run when a level starts, a checkpoint is reached or the game is paused, and drops
+/// the save once the game is cleared.
+pub fn save_system(
+    game_state: Res<GameState>,
+    level_state: Res<LevelState>,
+    stats: Res<RunStats>,
+    score: Res<Score>,
+    mut save_state: ResMut<SaveState>,
+    mut last: Local<Option<(usize, Option<usize>)>>,
+    players: Query<(&Player, &Transform)>,
+) {
+    if game_state.screen == Screen::Victory {
+        if save_state.saved.take().is_some() {
+            storage::remove(STORAGE_KEY);
+        }
+        return;
+    }
+
+    if game_state.screen != Screen::Playing && game_state.screen != Screen::Paused {
+        return;
+    }
+    if level_state.next.is_some() || level_state.respawn_pending || save_state.restore.is_some() {
+        return;
+    }
+
+    let progress = (level_state.current, level_state.checkpoint);
+    if *last == Some(progress) && !save_state.pending {
+        return;
+    }
+    *last = Some(progress);
+    save_state.pending = false;
+
+    let (player, transform) = match players.iter().next() {
+        Some(player) => player,
+        None => return,
+    };
+    if player.life == 0 {
+        return;
+    }
+
+    let save = SaveData {
+        version: VERSION,
+        level: level_state.current,
+        position: (transform.translation.x, transform.translation.y),
+        life: player.life,
+        defeated: level_state.defeated.iter().copied().collect(),
+        score: score.points,
+        checkpoint: level_state.checkpoint,
+        respawn: (level_state.respawn.x, level_state.respawn.y),
+        stats: stats.clone(),
+    };
+
+    match storage::save(STORAGE_KEY, &serde_json::to_string(&save).unwrap()) {
+        Ok(()) => debug!("Saved level {}", save.level + 1),
+        Err(e) => warn!("Couldn't save the game: {}", e),
+    }
+    save_state.saved = Some(save);
+}
+
+/// Puts the player back where the save was made once its level is loaded.
+pub fn restore_system(
+    mut save_state: ResMut<SaveState>,
+    mut level_state: ResMut<LevelState>,
+    mut players: Query<(&mut Player, &mut Char, &mut Transform)>,
+) {
+    if level_state.next.is_some() {
+        return;
+    }
+
+    let save = match save_state.restore.take() {
+        Some(save) => save,
+        None => return,
+    };
+
+    level_state.checkpoint = save.checkpoint;
+    level_state.respawn = Vec3::new(save.respawn.0, save.respawn.1, 0.0);
+
+    for (mut player, mut ch, mut transform) in players.iter_mut() {
+        player.life = save.life;
+        ch.velocity = Vec3::zero();
+        transform.translation = Vec3::new(save.position.0, save.position.1, 0.0);
+    }
+
+    info!("Continued level {}", save.level + 1);
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig,
+    screen::GameState,
+    timestep::{FixedTime, STEP},
+};
+
+/// Kills within this many seconds of each other build up a combo.
+const COMBO_WINDOW: f32 = 2.0;
+const MAX_COMBO: u32 = 5;
+
+#[derive(Debug)]
+pub struct Score {
+    pub points: u32,
+    /// Kills in the current combo.
+    pub combo: u32,
+    combo_timer: Timer,
+}
+
+impl Default for Score {
+    fn default() -> Self {
+        Self {
+            points: 0,
+            combo: 0,
+            combo_timer: Timer::from_seconds(COMBO_WINDOW, false),
+        }
+    }
+}
+
+impl Score {
+    pub fn multiplier(&self) -> u32 {
+        self.combo.max(1).min(MAX_COMBO)
+    }
+
+    /// Awards the points for defeating an enemy with `lgtm` LGTMs and returns them.
+    pub fn defeat(&mut self, lgtm: u32, boss: bool) -> u32 {
+        self.combo += 1;
+        self.combo_timer.reset();
+
+        let base = 100 + lgtm * 10;
+        let points = if boss { base * 5 } else { base } * self.multiplier();
+        self.points += points;
+        points
+    }
+}
+
+/// Marks the score text of the HUD.
+pub struct ScoreText;
+
+pub fn setup_score(
+    commands: &mut Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                // ui y grows upwards, so the end of the cross axis is the top
+                justify_content: JustifyContent::FlexEnd,
+                align_items: AlignItems::FlexEnd,
+                padding: Rect::all(Val::Px(16.0)),
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.0).into()),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle {
+                    text: Text {
+                        value: String::new(),
+                        font: asset_server.load(config.font.as_str()),
+                        style: TextStyle {
+                            font_size: 24.0,
+                            color: Color::WHITE,
+                            ..Default::default()
+                        },
+                    },
+                    ..Default::default()
+                })
+                .with(ScoreText);
+        });
+}
+
+pub fn combo_system(clock: Res<FixedTime>, game_state: Res<GameState>, mut score: ResMut<Score>) {
+    if !game_state.playing() || !clock.stepping() || score.combo == 0 {
+        return;
+    }
+
+    score.combo_timer.tick(STEP);
+    if score.combo_timer.finished {
+        score.combo = 0;
+    }
+}
+
+pub fn score_text_system(score: Res<Score>, mut texts: Query<(&ScoreText, &mut Text)>) {
+    let value = if score.combo > 1 {
+        format!("Score {}  x{}", score.points, score.multiplier())
+    } else {
+        format!("Score {}", score.points)
+    };
+
+    for (_, mut text) in texts.iter_mut() {
+        if text.value != value {
+            text.value = value.clone();
+        }
+    }
+}
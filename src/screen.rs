@@ -0,0 +1,279 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::GameConfig,
+    gamepad::Gamepads,
+    highscore::HighScores,
+    keybinds::{KeyBinds, RebindState},
+    level::LevelState,
+    replay::ReplayState,
+    save::SaveState,
+    score::Score,
+    stats::RunStats,
+    storage,
+    touch::TouchControls,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    Title,
+    Playing,
+    Paused,
+    GameOver,
+    Victory,
+    KeyBinds,
+}
+
+/// Which screen the game is on. Gameplay systems only run while playing.
+#[derive(Debug)]
+pub struct GameState {
+    pub screen: Screen,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self {
+            screen: Screen::Title,
+        }
+    }
+}
+
+impl GameState {
+    pub fn playing(&self) -> bool {
+        self.screen == Screen::Playing
+    }
+}
+
+/// Marks the UI nodes shown over the game on every screen but `Playing`.
+pub struct ScreenOverlay;
+
+/// Line of text on the overlay, from the top.
+pub struct ScreenText {
+    line: usize,
+}
+
+const SCREEN_LINES: usize = 15;
+
+pub fn setup_screen(
+    commands: &mut Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands
+        .spawn(CameraUiBundle::default())
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                // ui y grows upwards
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.add(Color::rgba(0.0, 0.0, 0.0, 0.5).into()),
+            ..Default::default()
+        })
+        .with(ScreenOverlay)
+        .with_children(|parent| {
+            let font = asset_server.load(config.font.as_str());
+            for line in 0..SCREEN_LINES {
+                parent
+                    .spawn(TextBundle {
+                        style: Style {
+                            margin: Rect::all(Val::Px(4.0)),
+                            ..Default::default()
+                        },
+                        text: Text {
+                            value: String::new(),
+                            font: font.clone(),
+                            style: TextStyle {
+                                font_size: if line == 0 { 40.0 } else { 24.0 },
+                                color: Color::WHITE,
+                                ..Default::default()
+                            },
+                        },
+                        ..Default::default()
+                    })
+                    .with(ScreenOverlay)
+                    .with(ScreenText { line });
+            }
+        });
+}
+
+pub fn screen_input_system(
+    keys: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    touch: Res<TouchControls>,
+    mut game_state: ResMut<GameState>,
+    mut level_state: ResMut<LevelState>,
+    mut stats: ResMut<RunStats>,
+    mut score: ResMut<Score>,
+    mut save_state: ResMut<SaveState>,
+    mut binds: ResMut<KeyBinds>,
+    mut rebind: ResMut<RebindState>,
+    mut replay: ResMut<ReplayState>,
+    mut back: Local<Option<Screen>>,
+) {
+    // tapping a menu screen outside the touch buttons works like enter
+    let tap = touch.tapped && !game_state.playing();
+    let pad_start = gamepads.just_pressed(&buttons, GamepadButtonType::Start);
+    let pause = keys.just_pressed(KeyCode::Escape)
+        || keys.just_pressed(KeyCode::P)
+        || pad_start
+        || touch.pause
+        || tap;
+    let start = keys.just_pressed(KeyCode::Return) || pad_start || tap;
+    let restart = keys.just_pressed(KeyCode::R);
+    let resume = keys.just_pressed(KeyCode::C);
+    let key_binds = keys.just_pressed(KeyCode::K);
+    let watch = keys.just_pressed(KeyCode::V);
+    let import = keys.just_pressed(KeyCode::I);
+
+    let mut new_run = false;
+
+    let next = match game_state.screen {
+        Screen::Title if start => {
+            level_state.restart(0);
+            new_run = true;
+            Screen::Playing
+        }
+        Screen::Title if watch && replay.saved.is_some() => {
+            replay.play();
+            level_state.restart(0);
+            new_run = true;
+            Screen::Playing
+        }
+        Screen::Title if import => {
+            replay.import();
+            return;
+        }
+        Screen::Title if resume && save_state.saved.is_some() => {
+            let save = save_state.saved.clone().unwrap();
+            level_state.next = Some(save.level);
+            level_state.timer = Timer::default();
+            level_state.defeated = save.defeated.iter().copied().collect();
+            *stats = save.stats.clone();
+            *score = Score::default();
+            score.points = save.score;
+            save_state.restore = Some(save);
+            Screen::Playing
+        }
+        Screen::Title | Screen::Paused if key_binds => {
+            *back = Some(game_state.screen);
+            *rebind = RebindState::default();
+            Screen::KeyBinds
+        }
+        Screen::KeyBinds => {
+            if !rebind.input(&keys, &mut binds) {
+                return;
+            }
+            back.take().unwrap_or(Screen::Title)
+        }
+        Screen::Playing if pause => {
+            save_state.pending = true;
+            Screen::Paused
+        }
+        Screen::Paused if pause => Screen::Playing,
+        Screen::Paused if restart => {
+            // the run no longer plays out from the start
+            replay.stop();
+            let current = level_state.current;
+            level_state.restart(current);
+            Screen::Playing
+        }
+        Screen::GameOver if start => {
+            // continue mid-level; defeated enemies stay defeated
+            level_state.respawn_pending = true;
+            Screen::Playing
+        }
+        Screen::GameOver if restart => {
+            level_state.restart(0);
+            new_run = true;
+            Screen::Playing
+        }
+        Screen::Victory if start => {
+            level_state.restart(0);
+            new_run = true;
+            Screen::Playing
+        }
+        _ => return,
+    };
+
+    if new_run {
+        *stats = RunStats {
+            started: storage::now(),
+            ..Default::default()
+        };
+        *score = Score::default();
+        if !replay.playing() {
+            replay.record();
+        }
+    }
+
+    info!("Screen: {:?} -> {:?}", game_state.screen, next);
+    game_state.screen = next;
+}
+
+pub fn screen_overlay_system(
+    game_state: Res<GameState>,
+    config: Res<GameConfig>,
+    stats: Res<RunStats>,
+    score: Res<Score>,
+    high_scores: Res<HighScores>,
+    save_state: Res<SaveState>,
+    replay: Res<ReplayState>,
+    binds: Res<KeyBinds>,
+    rebind: Res<RebindState>,
+    mut overlays: Query<(&ScreenOverlay, &mut Draw)>,
+    mut texts: Query<(&ScreenText, &mut Text)>,
+) {
+    let lines = match game_state.screen {
+        Screen::Title => {
+            let mut lines = vec![config.title.clone(), "Press Enter to start".to_string()];
+            if let Some(save) = &save_state.saved {
+                lines.push(format!("C to continue from level {}", save.level + 1));
+            }
+            lines.push("K to configure keys".to_string());
+            if replay.saved.is_some() {
+                lines.push("V to watch the last run".to_string());
+            }
+            lines.push("I to import a replay".to_string());
+            lines.extend(high_scores.lines(5));
+            lines
+        }
+        Screen::Playing => vec![],
+        Screen::Paused => vec![
+            "Paused".to_string(),
+            "Esc to resume, R to retry the level".to_string(),
+            "K to configure keys".to_string(),
+        ],
+        Screen::KeyBinds => rebind.lines(&binds),
+        Screen::GameOver => {
+            let mut lines = vec!["Game over".to_string(), format!("Score: {}", score.points)];
+            lines.extend(stats.lines());
+            lines.push("Enter to continue, R to restart".to_string());
+            lines.extend(high_scores.lines(5));
+            lines
+        }
+        Screen::Victory => {
+            let mut lines = vec!["Clear!".to_string(), format!("Score: {}", score.points)];
+            lines.extend(stats.lines());
+            lines.push("Press Enter to play again".to_string());
+            lines.extend(high_scores.lines(5));
+            lines
+        }
+    };
+
+    for (_, mut draw) in overlays.iter_mut() {
+        draw.is_visible = !game_state.playing();
+    }
+
+    for (text_line, mut text) in texts.iter_mut() {
+        let value = lines.get(text_line.line).map(|s| s.as_str()).unwrap_or("");
+        if text.value != value {
+            text.value = value.to_string();
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::screen::GameState;
+
+/// Statistics of the current run, shown on the results screen.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct RunStats {
+    /// Play time in seconds, excluding pauses and menus.
+    pub time: f32,
+    pub damage_taken: u32,
+    pub attacks_fired: u32,
+    pub attacks_hit: u32,
+    pub enemies_defeated: u32,
+    /// Start of the run in seconds since the unix epoch.
+    pub started: u64,
+}
+
+/// Formats seconds as `m:ss.s`.
+pub fn time_text(time: f32) -> String {
+    let minutes = (time / 60.0) as u32;
+    format!("{}:{:04.1}", minutes, time - minutes as f32 * 60.0)
+}
+
+impl RunStats {
+    /// Result lines of the run.
+    pub fn lines(&self) -> Vec<String> {
+        let accuracy = if self.attacks_fired > 0 {
+            self.attacks_hit * 100 / self.attacks_fired
+        } else {
+            0
+        };
+
+        vec![
+            format!("Time: {}", time_text(self.time)),
+            format!("Damage taken: {}", self.damage_taken),
+            format!(
+                "Attacks: {} fired, {} hit ({}%)",
+                self.attacks_fired, self.attacks_hit, accuracy
+            ),
+            format!("Enemies defeated: {}", self.enemies_defeated),
+        ]
+    }
+}
+
+pub fn run_time_system(time: Res<Time>, game_state: Res<GameState>, mut stats: ResMut<RunStats>) {
+    if game_state.playing() {
+        stats.time += time.delta_seconds;
+    }
+}
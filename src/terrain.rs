@@ -0,0 +1,559 @@
+use bevy::{prelude::*, sprite::SpriteResizeMode, type_registry::TypeUuid};
+use derive_new::new;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::{
+    atlas::AtlasBuilder,
+    camera::CameraState,
+    config::GameConfig,
+    level::LevelEntity,
+    physics::to_rect,
+    plugin::{add_stages, add_tile_maps},
+    tilemesh,
+    timestep::Interpolated,
+};
+
+#[derive(Debug, new)]
+pub struct Terrain {
+    pub kind: ShapeKind,
+    rects: Vec<Rect<f32>>,
+    pub slope: Option<(f32, f32)>,
+}
+
+impl Terrain {
+    fn from_shape(shape: &TileShape) -> Self {
+        let rects = if shape.rects.is_empty() {
+            vec![to_rect(&Vec3::zero(), &Vec2::new(16.0, 16.0))]
+        } else {
+            shape
+                .rects
+                .iter()
+                .map(|r| to_rect(&Vec3::new(r.x, r.y, 0.0), &Vec2::new(r.width, r.height)))
+                .collect()
+        };
+        Self::new(shape.kind, rects, shape.slope)
+    }
+
+    fn rects(&self, origin: Vec3) -> impl Iterator<Item = Rect<f32>> + '_ {
+        self.rects.iter().map(move |r| Rect {
+            left: origin.x + r.left,
+            right: origin.x + r.right,
+            bottom: origin.y + r.bottom,
+            top: origin.y + r.top,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShapeKind {
+    Solid,
+    OneWay,
+    Slope,
+    Hazard,
+    None,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ShapeRect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TileShape {
+    pub kind: ShapeKind,
+    pub rects: Vec<ShapeRect>,
+    pub slope: Option<(f32, f32)>,
+}
+
+impl Default for TileShape {
+    fn default() -> Self {
+        Self {
+            kind: ShapeKind::Solid,
+            rects: vec![],
+            slope: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemyAi {
+    Wander,
+    Patrol,
+    Guard,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ObjectKind {
+    PlayerSpawn,
+    Enemy {
+        ai: EnemyAi,
+        patrol: Option<(f32, f32)>,
+        boss: bool,
+    },
+    Checkpoint,
+}
+
+/// Object placed in the map, in map pixels from the top-left corner of the map.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MapObject {
+    pub name: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(flatten)]
+    pub kind: ObjectKind,
+}
+
+impl MapObject {
+    /// Translation of a character standing at the bottom-left corner of the object.
+    pub fn position(&self) -> Vec3 {
+        // map rows grow downwards from the top edge of tile row 0, which is at y = 16
+        Vec3::new(self.x, 16.0 - self.y - self.height, 0.0)
+    }
+}
+
+#[derive(Debug, new)]
+pub struct Parallax {
+    origin: Vec3,
+    factor: Vec2,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct TileLayer {
+    pub name: String,
+    pub order: usize,
+    pub visible: bool,
+    pub opacity: f32,
+    pub parallax: (f32, f32),
+    pub solid: bool,
+    pub map: Vec<Vec<u32>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, TypeUuid)]
+#[uuid = "69bfe4d8-c369-4dc9-abf2-6c1f1e88cebc"]
+pub struct TileMap {
+    pub layers: Vec<TileLayer>,
+    #[serde(default)]
+    pub shapes: HashMap<u32, TileShape>,
+    #[serde(default)]
+    pub objects: Vec<MapObject>,
+}
+
+impl TileMap {
+    pub fn player_spawn(&self) -> Option<Vec3> {
+        self.objects
+            .iter()
+            .find(|o| matches!(o.kind, ObjectKind::PlayerSpawn))
+            .map(|o| o.position())
+    }
+
+    pub fn enemy_spawns(&self) -> Vec<&MapObject> {
+        self.objects
+            .iter()
+            .filter(|o| matches!(o.kind, ObjectKind::Enemy { .. }))
+            .collect()
+    }
+
+    pub fn checkpoints(&self) -> impl Iterator<Item = &MapObject> {
+        self.objects
+            .iter()
+            .filter(|o| matches!(o.kind, ObjectKind::Checkpoint))
+    }
+}
+
+/// Terrain streaming around the camera.
+#[derive(Debug)]
+pub struct ChunkSettings {
+    /// Side length of a chunk in tiles.
+    pub size: usize,
+    /// Distance beyond the camera view within which chunks are loaded.
+    pub margin: f32,
+    /// Extra distance a loaded chunk may move out of the margin before it's unloaded.
+    pub hysteresis: f32,
+}
+
+impl Default for ChunkSettings {
+    fn default() -> Self {
+        Self {
+            size: 16,
+            margin: 64.0,
+            hysteresis: 128.0,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Chunk {
+    tiles: Vec<(usize, usize, u32)>,
+    mesh: Option<Handle<Mesh>>,
+    entity: Option<Entity>,
+    loaded: bool,
+}
+
+#[derive(Debug, Default)]
+struct LayerInfo {
+    z: f32,
+    material: Handle<ColorMaterial>,
+    parallax: Vec2,
+    columns: usize,
+    rows: usize,
+    chunks: Vec<Chunk>,
+}
+
+impl LayerInfo {
+    /// Columns and rows of the chunks overlapping `area` as seen from `camera`.
+    fn chunk_span(
+        &self,
+        area: &Rect<f32>,
+        camera: Vec3,
+        size: usize,
+    ) -> (Range<usize>, Range<usize>) {
+        fn span(min: f32, max: f32, size: usize, len: usize) -> Range<usize> {
+            if max < 0.0 {
+                return 0..0;
+            }
+            let min = min.max(0.0) as usize / size;
+            let max = (max as usize / size + 1).min(len);
+            min..max.max(min)
+        }
+
+        // tiles of parallax layers are drawn shifted by the camera position
+        let shift_x = camera.x * (1.0 - self.parallax.x);
+        let shift_y = camera.y * (1.0 - self.parallax.y);
+
+        // map rows grow downwards while world y grows upwards
+        (
+            span(
+                (area.left - shift_x) / 16.0,
+                (area.right - shift_x) / 16.0,
+                size,
+                self.columns,
+            ),
+            span(
+                -(area.top - shift_y) / 16.0,
+                -(area.bottom - shift_y) / 16.0 + 1.0,
+                size,
+                self.rows,
+            ),
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TileInfo {
+    center: Vec3,
+    chunk_size: usize,
+    layers: Vec<LayerInfo>,
+    loaded: Vec<(usize, usize)>,
+    atlas_handle: Handle<TextureAtlas>,
+    texture: Handle<Texture>,
+    timer: Timer,
+}
+
+impl TileInfo {
+    /// Replaces the layers with those of `tilemap`, split into chunks of `size` tiles.
+    pub fn load(&mut self, tilemap: &TileMap, size: usize, materials: &mut Assets<ColorMaterial>) {
+        // Layers up to the topmost solid one are drawn behind characters, the rest in front.
+        let ground = tilemap
+            .layers
+            .iter()
+            .filter(|l| l.solid)
+            .map(|l| l.order)
+            .max()
+            .unwrap_or(0);
+
+        let texture = self.texture.clone();
+
+        self.layers = tilemap
+            .layers
+            .iter()
+            .map(|l| {
+                let z = if l.order <= ground {
+                    l.order as f32 - ground as f32 - 1.0
+                } else {
+                    (l.order - ground) as f32
+                };
+
+                let width = l.map.iter().map(|v| v.len()).max().unwrap_or(0);
+                let columns = (width + size - 1) / size;
+                let rows = (l.map.len() + size - 1) / size;
+
+                let mut chunks: Vec<Chunk> =
+                    (0..columns * rows).map(|_| Chunk::default()).collect();
+
+                if l.visible {
+                    for (y, v) in l.map.iter().enumerate() {
+                        for (x, &i) in v.iter().enumerate() {
+                            if i != 0 {
+                                chunks[y / size * columns + x / size].tiles.push((x, y, i));
+                            }
+                        }
+                    }
+                }
+
+                LayerInfo {
+                    z,
+                    material: materials.add(ColorMaterial {
+                        color: Color::rgba(1.0, 1.0, 1.0, l.opacity),
+                        texture: Some(texture.clone()),
+                    }),
+                    parallax: Vec2::new(l.parallax.0, l.parallax.1),
+                    columns,
+                    rows,
+                    chunks,
+                }
+            })
+            .collect();
+        self.chunk_size = size;
+        self.loaded.clear();
+    }
+}
+
+/// Collision shapes of the solid layers, indexed by map cell.
+#[derive(Debug, Default)]
+pub struct TileGrid {
+    width: usize,
+    height: usize,
+    cells: Vec<Vec<u32>>,
+    terrains: HashMap<u32, Terrain>,
+}
+
+impl TileGrid {
+    pub fn new(layers: &[TileLayer], shapes: &HashMap<u32, TileShape>) -> Self {
+        let height = layers.iter().map(|l| l.map.len()).max().unwrap_or(0);
+        let width = layers
+            .iter()
+            .flat_map(|l| l.map.iter())
+            .map(|v| v.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut cells = vec![vec![]; width * height];
+        let mut terrains = HashMap::new();
+
+        for layer in layers.iter().filter(|l| l.solid) {
+            for (y, v) in layer.map.iter().enumerate() {
+                for (x, &i) in v.iter().enumerate() {
+                    if i == 0 {
+                        continue;
+                    }
+
+                    cells[y * width + x].push(i);
+                    terrains.entry(i).or_insert_with(|| {
+                        Terrain::from_shape(&shapes.get(&i).cloned().unwrap_or_default())
+                    });
+                }
+            }
+        }
+
+        Self {
+            width,
+            height,
+            cells,
+            terrains,
+        }
+    }
+
+    /// Terrain rects in the cells overlapping `area` and its neighbours, in world coordinates.
+    pub fn around<'a>(
+        &'a self,
+        area: &Rect<f32>,
+    ) -> impl Iterator<Item = (&'a Terrain, Rect<f32>)> + 'a {
+        fn span(min: f32, max: f32, len: usize) -> Range<usize> {
+            let min = (min.floor() as isize - 1).max(0) as usize;
+            let max = ((max.floor() as isize + 2).max(0) as usize).min(len);
+            min..max.max(min)
+        }
+
+        // map rows grow downwards while world y grows upwards
+        let xs = span(area.left / 16.0, area.right / 16.0, self.width);
+        let ys = span(-area.top / 16.0, -area.bottom / 16.0, self.height);
+
+        ys.flat_map(move |y| xs.clone().map(move |x| (x, y)))
+            .flat_map(move |(x, y)| {
+                let origin = Vec3::new(x as f32 * 16.0, y as f32 * -16.0, 0.0);
+                self.cells[y * self.width + x]
+                    .iter()
+                    .filter_map(move |i| self.terrains.get(i))
+                    .flat_map(move |t| t.rects(origin).map(move |r| (t, r)))
+            })
+    }
+}
+
+pub fn setup_terrain(
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    mut tileinfo: ResMut<TileInfo>,
+    mut atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    let atlas_handle = AtlasBuilder::load(
+        &asset_server,
+        Vec2::new(16.0, 16.0),
+        Vec2::new(176.0, 135.0),
+        &config.tile_texture,
+    )
+    .build(&mut atlases);
+
+    tileinfo.texture = atlases
+        .get(&atlas_handle)
+        .map(|atlas| atlas.texture.clone())
+        .unwrap_or_default();
+    tileinfo.atlas_handle = atlas_handle;
+    tileinfo.timer = Timer::from_seconds(0.2, true);
+}
+
+pub fn load_terrain_system(
+    time: Res<Time>,
+    commands: &mut Commands,
+    camera_state: Res<CameraState>,
+    settings: Res<ChunkSettings>,
+    atlases: Res<Assets<TextureAtlas>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut tileinfo: ResMut<TileInfo>,
+) {
+    tileinfo.timer.tick(time.delta_seconds);
+    if !tileinfo.timer.finished {
+        return;
+    }
+
+    if camera_state.transform.translation == tileinfo.center && !tileinfo.loaded.is_empty() {
+        return;
+    }
+    tileinfo.center = camera_state.transform.translation;
+
+    let tileinfo = &mut *tileinfo;
+    let atlas = match atlases.get(&tileinfo.atlas_handle) {
+        Some(atlas) => atlas,
+        None => return,
+    };
+    let size = tileinfo.chunk_size;
+    let camera = camera_state.transform.translation;
+
+    let load_view = camera_state.view(settings.margin);
+    let keep_view = camera_state.view(settings.margin + settings.hysteresis);
+
+    let mut loaded_count = 0;
+    let mut unloaded_count = 0;
+
+    let layers = &mut tileinfo.layers;
+    tileinfo.loaded.retain(|&(l, c)| {
+        let layer = &mut layers[l];
+
+        let (xs, ys) = layer.chunk_span(&keep_view, camera, size);
+        if xs.contains(&(c % layer.columns)) && ys.contains(&(c / layer.columns)) {
+            return true;
+        }
+
+        let chunk = &mut layer.chunks[c];
+        if let Some(entity) = chunk.entity.take() {
+            commands.despawn(entity);
+        }
+        chunk.loaded = false;
+
+        unloaded_count += 1;
+        false
+    });
+
+    for (l, layer) in tileinfo.layers.iter_mut().enumerate() {
+        let (xs, ys) = layer.chunk_span(&load_view, camera, size);
+
+        for cy in ys {
+            for cx in xs.clone() {
+                let c = cy * layer.columns + cx;
+                let chunk = &mut layer.chunks[c];
+                if chunk.loaded {
+                    continue;
+                }
+
+                if !chunk.tiles.is_empty() {
+                    let tiles = &chunk.tiles;
+                    let mesh = chunk
+                        .mesh
+                        .get_or_insert_with(|| meshes.add(tilemesh::build(tiles, atlas, 16.0)))
+                        .clone();
+
+                    let origin = Vec3::new(0.0, 0.0, layer.z);
+                    let translation = Vec3::new(
+                        camera.x * (1.0 - layer.parallax.x),
+                        camera.y * (1.0 - layer.parallax.y),
+                        origin.z,
+                    );
+
+                    // the mesh is in world coordinates, so draw it unscaled
+                    commands
+                        .spawn(SpriteBundle {
+                            sprite: Sprite {
+                                size: Vec2::one(),
+                                resize_mode: SpriteResizeMode::Manual,
+                            },
+                            mesh,
+                            material: layer.material.clone(),
+                            transform: Transform::from_translation(translation),
+                            ..Default::default()
+                        })
+                        .with(LevelEntity);
+
+                    if layer.parallax != Vec2::one() {
+                        commands
+                            .with(Parallax::new(origin, layer.parallax))
+                            .with(Interpolated::default());
+                    }
+
+                    chunk.entity = commands.current_entity();
+                }
+
+                chunk.loaded = true;
+                tileinfo.loaded.push((l, c));
+                loaded_count += 1;
+            }
+        }
+    }
+
+    if loaded_count > 0 || unloaded_count > 0 {
+        debug!(
+            "Chunks loaded: {}, unloaded: {} (current: {})",
+            loaded_count,
+            unloaded_count,
+            tileinfo.loaded.len()
+        );
+    }
+}
+
+pub fn parallax_system(
+    camera_state: Res<CameraState>,
+    mut query: Query<(&Parallax, &mut Transform)>,
+) {
+    let camera = camera_state.transform.translation;
+
+    for (parallax, mut transform) in query.iter_mut() {
+        transform.translation.x = parallax.origin.x + camera.x * (1.0 - parallax.factor.x);
+        transform.translation.y = parallax.origin.y + camera.y * (1.0 - parallax.factor.y);
+    }
+}
+
+/// Terrain chunks of the tile maps and the parallax background.
+pub struct TerrainPlugin;
+
+impl Plugin for TerrainPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        add_stages(app);
+        add_tile_maps(app);
+        app.init_resource::<GameConfig>()
+            .init_resource::<CameraState>()
+            .init_resource::<TileInfo>()
+            .init_resource::<ChunkSettings>()
+            .add_startup_system(setup_terrain)
+            .add_system(load_terrain_system)
+            // placed against the camera of this frame
+            .add_system_to_stage(stage::POST_UPDATE, parallax_system);
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, pipeline::PrimitiveTopology},
+};
+
+/// Builds one mesh drawing all `tiles`, given as (map column, map row, gid), with UVs from `atlas`.
+///
+/// Vertices are in world coordinates, so the mesh is meant to be drawn by a 1x1 sprite.
+pub fn build(tiles: &[(usize, usize, u32)], atlas: &TextureAtlas, tile_size: f32) -> Mesh {
+    let mut positions = Vec::with_capacity(tiles.len() * 4);
+    let mut normals: Vec<[f32; 3]> = Vec::with_capacity(tiles.len() * 4);
+    let mut uvs = Vec::with_capacity(tiles.len() * 4);
+    let mut indices = Vec::with_capacity(tiles.len() * 6);
+
+    let half = tile_size / 2.0;
+
+    for &(x, y, i) in tiles {
+        let rect = match atlas.textures.get(i as usize - 1) {
+            Some(rect) => rect,
+            None => continue,
+        };
+
+        let x = x as f32 * tile_size;
+        let y = y as f32 * -tile_size;
+
+        let min = rect.min / atlas.size;
+        let max = rect.max / atlas.size;
+
+        let base = positions.len() as u32;
+
+        // same layout as a sprite quad; texture v grows downwards
+        positions.extend(&[
+            [x - half, y - half, 0.0],
+            [x - half, y + half, 0.0],
+            [x + half, y + half, 0.0],
+            [x + half, y - half, 0.0],
+        ]);
+        normals.extend(&[[0.0, 0.0, 1.0]; 4]);
+        uvs.extend(&[
+            [min.x, max.y],
+            [min.x, min.y],
+            [max.x, min.y],
+            [max.x, max.y],
+        ]);
+        indices.extend(&[base, base + 2, base + 1, base, base + 3, base + 2]);
+    }
+
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.set_indices(Some(Indices::U32(indices)));
+    mesh
+}
@@ -0,0 +1,134 @@
+//! Fixed-timestep clock of the gameplay simulation, and interpolation of what moves between steps.
+
+use bevy::prelude::*;
+
+use crate::screen::GameState;
+
+/// Length of a simulation step, in seconds.
+pub const STEP: f32 = 1.0 / 60.0;
+
+/// Most steps run in one frame; time beyond that is dropped so a stall doesn't snowball.
+pub const MAX_STEPS: u32 = 8;
+
+/// Stages of the steps of a frame, one row per step. The step starts, characters decide where to
+/// go and fire, characters and attacks move, then attacks hit; the gameplay systems run in every
+/// row, doing nothing in the rows past the steps of the frame.
+pub const STEP_STAGES: [[&str; 4]; MAX_STEPS as usize] = [
+    ["step 1", "step 1 decide", "step 1 move", "step 1 hit"],
+    ["step 2", "step 2 decide", "step 2 move", "step 2 hit"],
+    ["step 3", "step 3 decide", "step 3 move", "step 3 hit"],
+    ["step 4", "step 4 decide", "step 4 move", "step 4 hit"],
+    ["step 5", "step 5 decide", "step 5 move", "step 5 hit"],
+    ["step 6", "step 6 decide", "step 6 move", "step 6 hit"],
+    ["step 7", "step 7 decide", "step 7 move", "step 7 hit"],
+    ["step 8", "step 8 decide", "step 8 move", "step 8 hit"],
+];
+
+/// Moves longer than this are teleports, which are drawn without interpolation.
+const SNAP_DISTANCE: f32 = 64.0;
+
+#[derive(Debug, Default)]
+pub struct FixedTime {
+    accumulator: f32,
+    /// Steps to simulate this frame.
+    pub steps: u32,
+    /// Steps of this frame started so far.
+    step: u32,
+    /// A step is running.
+    stepping: bool,
+    /// Steps simulated by the last frame that simulated any.
+    span: u32,
+    /// Fraction of a step left in the accumulator.
+    alpha: f32,
+}
+
+impl FixedTime {
+    /// A step is running, in which the gameplay systems advance the game by `STEP`.
+    pub fn stepping(&self) -> bool {
+        self.stepping
+    }
+}
+
+/// Draws the entity between its translations before and after the last simulated steps.
+#[derive(Debug, Default)]
+pub struct Interpolated {
+    previous: Option<Vec3>,
+    /// Drawn this far from the translation.
+    offset: Vec3,
+}
+
+impl Interpolated {
+    /// Draws the entity `offset` away from its translation.
+    pub fn offset(offset: Vec3) -> Self {
+        Self {
+            previous: None,
+            offset,
+        }
+    }
+}
+
+pub fn fixed_time_system(
+    time: Res<Time>,
+    game_state: Res<GameState>,
+    mut clock: ResMut<FixedTime>,
+) {
+    clock.step = 0;
+    clock.stepping = false;
+
+    if !game_state.playing() {
+        clock.steps = 0;
+        return;
+    }
+
+    // a little slack so frames of exactly one step don't round down to none
+    clock.accumulator += time.delta_seconds;
+    let steps = ((clock.accumulator + 1e-4) / STEP) as u32;
+    clock.accumulator = (clock.accumulator - steps as f32 * STEP).max(0.0);
+
+    clock.steps = steps.min(MAX_STEPS);
+    if clock.steps > 0 {
+        clock.span = clock.steps;
+    }
+    clock.alpha = (clock.accumulator / STEP).min(1.0);
+}
+
+/// Starts the next step of the frame, if any is left. Runs alone in the first stage of every step.
+pub fn step_system(mut clock: ResMut<FixedTime>) {
+    clock.stepping = clock.step < clock.steps;
+    if clock.stepping {
+        clock.step += 1;
+    }
+}
+
+/// Remembers where interpolated entities are before the steps of this frame.
+pub fn interpolation_snapshot_system(
+    clock: Res<FixedTime>,
+    mut query: Query<(&mut Interpolated, &Transform)>,
+) {
+    if clock.steps == 0 {
+        return;
+    }
+
+    for (mut interpolated, transform) in query.iter_mut() {
+        interpolated.previous = Some(transform.translation);
+    }
+}
+
+/// Draws interpolated entities as they were the leftover fraction of a step ago, so motion is
+/// smooth whatever the frame rate. Only the drawn transform changes; the simulated one is kept.
+pub fn interpolation_system(
+    clock: Res<FixedTime>,
+    mut query: Query<(&Interpolated, &Transform, &mut GlobalTransform)>,
+) {
+    let back = (1.0 - clock.alpha) / clock.span.max(1) as f32;
+
+    for (interpolated, transform, mut global) in query.iter_mut() {
+        let current = transform.translation;
+        let previous = match interpolated.previous {
+            Some(previous) if (current - previous).length() <= SNAP_DISTANCE => previous,
+            _ => current,
+        };
+
+        global.translation = current + (previous - current) * back + interpolated.offset;
+    }
+}
@@ -0,0 +1,218 @@
+use bevy::input::touch::{TouchInput, TouchPhase};
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::{config::GameConfig, keybinds::Action, screen::GameState};
+
+const BUTTON_SIZE: f32 = 64.0;
+const MARGIN: f32 = 24.0;
+
+/// On-screen controls, shown during play once the first touch comes in.
+#[derive(Debug, Default)]
+pub struct TouchControls {
+    pub enabled: bool,
+    /// Current touches by id, in ui coordinates.
+    touches: HashMap<u64, Vec2>,
+    pressed: HashSet<Action>,
+    /// A touch started outside the buttons this frame.
+    pub tapped: bool,
+    /// A touch started on the pause button this frame.
+    pub pause: bool,
+}
+
+impl TouchControls {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+}
+
+#[derive(Debug)]
+pub struct TouchButton {
+    /// Action held down by the button, or `None` for the pause button.
+    action: Option<Action>,
+}
+
+/// Marks every entity of the on-screen controls.
+#[derive(Debug)]
+pub struct TouchControl;
+
+pub fn setup_touch(
+    commands: &mut Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<GameConfig>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let font = asset_server.load(config.font.as_str());
+    let material = materials.add(Color::rgba(1.0, 1.0, 1.0, 0.2).into());
+
+    // d-pad on the bottom left, jump and attack on the bottom right, pause on the top right
+    let step = BUTTON_SIZE + 8.0;
+    let buttons = [
+        (
+            Some(Action::Left),
+            "◀",
+            Val::Px(MARGIN),
+            Val::Auto,
+            Val::Auto,
+            Val::Px(MARGIN + step),
+        ),
+        (
+            Some(Action::Right),
+            "▶",
+            Val::Px(MARGIN + step * 2.0),
+            Val::Auto,
+            Val::Auto,
+            Val::Px(MARGIN + step),
+        ),
+        (
+            Some(Action::Up),
+            "▲",
+            Val::Px(MARGIN + step),
+            Val::Auto,
+            Val::Auto,
+            Val::Px(MARGIN + step * 2.0),
+        ),
+        (
+            Some(Action::Down),
+            "▼",
+            Val::Px(MARGIN + step),
+            Val::Auto,
+            Val::Auto,
+            Val::Px(MARGIN),
+        ),
+        (
+            Some(Action::Up),
+            "Jump",
+            Val::Auto,
+            Val::Px(MARGIN + step),
+            Val::Auto,
+            Val::Px(MARGIN),
+        ),
+        (
+            Some(Action::Attack),
+            "Attack",
+            Val::Auto,
+            Val::Px(MARGIN),
+            Val::Auto,
+            Val::Px(MARGIN + step / 2.0),
+        ),
+        (
+            None,
+            "II",
+            Val::Auto,
+            Val::Px(MARGIN),
+            Val::Px(MARGIN),
+            Val::Auto,
+        ),
+    ];
+
+    for &(action, label, left, right, top, bottom) in buttons.iter() {
+        commands
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        left,
+                        right,
+                        top,
+                        bottom,
+                    },
+                    size: Size::new(Val::Px(BUTTON_SIZE), Val::Px(BUTTON_SIZE)),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                material: material.clone(),
+                draw: Draw {
+                    is_visible: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .with(TouchButton { action })
+            .with(TouchControl)
+            .with_children(|parent| {
+                parent
+                    .spawn(TextBundle {
+                        text: Text {
+                            value: label.to_string(),
+                            font: font.clone(),
+                            style: TextStyle {
+                                font_size: if label.len() > 3 { 16.0 } else { 28.0 },
+                                color: Color::WHITE,
+                                ..Default::default()
+                            },
+                        },
+                        draw: Draw {
+                            is_visible: false,
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    })
+                    .with(TouchControl);
+            });
+    }
+}
+
+pub fn touch_input_system(
+    mut reader: Local<EventReader<TouchInput>>,
+    events: Res<Events<TouchInput>>,
+    windows: Res<Windows>,
+    mut controls: ResMut<TouchControls>,
+    buttons: Query<(&TouchButton, &Node, &GlobalTransform, &Draw)>,
+) {
+    let height = windows.get_primary().map_or(0.0, |w| w.height() as f32);
+    // hidden buttons let touches through
+    let hit = |position: Vec2, node: &Node, transform: &GlobalTransform, draw: &Draw| {
+        let center = transform.translation;
+        draw.is_visible
+            && (position.x - center.x).abs() <= node.size.x / 2.0
+            && (position.y - center.y).abs() <= node.size.y / 2.0
+    };
+
+    controls.tapped = false;
+    controls.pause = false;
+    for event in reader.iter(&events) {
+        controls.enabled = true;
+
+        // touches start at the top of the window, ui at the bottom
+        let position = Vec2::new(event.position.x, height - event.position.y);
+        match event.phase {
+            TouchPhase::Started => {
+                controls.touches.insert(event.id, position);
+                match buttons
+                    .iter()
+                    .find(|(_, node, t, d)| hit(position, node, t, d))
+                {
+                    Some((button, _, _, _)) => controls.pause |= button.action.is_none(),
+                    None => controls.tapped = true,
+                }
+            }
+            TouchPhase::Moved => {
+                controls.touches.insert(event.id, position);
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                controls.touches.remove(&event.id);
+            }
+        }
+    }
+
+    let pressed = buttons
+        .iter()
+        .filter(|(_, node, t, d)| controls.touches.values().any(|&p| hit(p, node, t, d)))
+        .filter_map(|(button, _, _, _)| button.action)
+        .collect();
+    controls.pressed = pressed;
+}
+
+pub fn touch_visibility_system(
+    controls: Res<TouchControls>,
+    game_state: Res<GameState>,
+    mut query: Query<(&TouchControl, &mut Draw)>,
+) {
+    let visible = controls.enabled && game_state.playing();
+
+    for (_, mut draw) in query.iter_mut() {
+        draw.is_visible = visible;
+    }
+}
@@ -0,0 +1,292 @@
+//! Gameplay tests, driven through the headless harness of the `harness` feature. Without it they
+//! are left out, and `tests/no_harness.rs` says so.
+#![cfg(feature = "harness")]
+
+use bevy::prelude::*;
+use mcp2::{harness::Harness, Attack, Char, Dir, Enemy, Player, State};
+
+/// Solid floor whose top is at y = -48.
+const FLOOR: &[&str] = &["", "", "", "", "########################################"];
+
+const FLOOR_TOP: f32 = -48.0;
+
+/// `FLOOR` with a one-way platform over x = 64..128, whose top is at y = -16.
+const PLATFORM: &[&str] = &[
+    "",
+    "",
+    "    ====",
+    "",
+    "########################################",
+];
+
+const PLATFORM_TOP: f32 = -16.0;
+
+fn frames(seconds: f32, frame_time: f32) -> usize {
+    (seconds / frame_time).ceil() as usize
+}
+
+#[test]
+fn player_falls_onto_the_floor() {
+    let mut h = Harness::new();
+    h.map(FLOOR);
+    let player = h.spawn_player(Vec3::new(32.0, 0.0, 0.0));
+
+    h.run(60);
+
+    assert!(h.get(player, |ch: &Char| ch.on_ground));
+    assert_eq!(h.translation(player).y, FLOOR_TOP);
+}
+
+#[test]
+fn player_walks_while_the_key_is_held() {
+    let mut h = Harness::new();
+    h.map(FLOOR);
+    let player = h.spawn_player(Vec3::new(32.0, FLOOR_TOP, 0.0));
+    h.run(10);
+
+    let start = h.translation(player).x;
+    h.press(KeyCode::D);
+    h.run(60);
+
+    let walked = h.translation(player).x - start;
+    assert!((90.0..=100.0).contains(&walked), "walked {}", walked);
+    h.get(player, |ch: &Char| {
+        assert_eq!(ch.dir, Dir::Right);
+        assert_eq!(ch.state, State::Run);
+    });
+
+    h.release(KeyCode::D);
+    h.run(2);
+
+    let stopped = h.translation(player).x;
+    h.run(10);
+    assert_eq!(h.translation(player).x, stopped);
+}
+
+#[test]
+fn reaching_the_exit_completes_the_level() {
+    let mut h = Harness::new();
+    h.map(FLOOR);
+    h.exit(Vec3::new(128.0, FLOOR_TOP, 0.0), Vec2::new(32.0, 48.0));
+    h.spawn_player(Vec3::new(32.0, FLOOR_TOP, 0.0));
+    h.run(10);
+    assert!(!h.level_complete());
+
+    h.press(KeyCode::D);
+    h.run(60);
+
+    assert!(h.level_complete());
+}
+
+#[test]
+fn player_walks_up_a_slope() {
+    let mut h = Harness::new();
+    h.map(&["", "", "", "      /#####", "############"]);
+    let player = h.spawn_player(Vec3::new(32.0, FLOOR_TOP, 0.0));
+    h.run(10);
+
+    h.press(KeyCode::D);
+    let mut on_slope = false;
+    for _ in 0..frames(1.2, 1.0 / 60.0) {
+        h.run(1);
+        let y = h.translation(player).y;
+        on_slope |= FLOOR_TOP < y && y < FLOOR_TOP + 16.0;
+    }
+
+    // climbs gradually instead of being stopped by a step
+    assert!(on_slope);
+    assert!(h.translation(player).x > 112.0);
+    assert_eq!(h.translation(player).y, FLOOR_TOP + 16.0);
+    assert!(h.get(player, |ch: &Char| ch.on_ground));
+}
+
+#[test]
+fn player_jumps_through_a_one_way_platform_and_drops_down() {
+    let mut h = Harness::new();
+    h.map(PLATFORM);
+    let player = h.spawn_player(Vec3::new(88.0, FLOOR_TOP, 0.0));
+    h.run(30);
+
+    // up through it from below, landing on top
+    h.press(KeyCode::W);
+    h.run(1);
+    h.release(KeyCode::W);
+    h.run(90);
+    assert_eq!(h.translation(player).y, PLATFORM_TOP);
+    assert!(h.get(player, |ch: &Char| ch.on_ground));
+
+    // down through it while S is held
+    h.press(KeyCode::S);
+    h.run(60);
+    h.release(KeyCode::S);
+    assert_eq!(h.translation(player).y, FLOOR_TOP);
+    assert!(h.get(player, |ch: &Char| ch.on_ground));
+}
+
+fn jump_height(frame_time: f32) -> f32 {
+    let mut h = Harness::new();
+    h.frame_time(frame_time);
+    h.map(FLOOR);
+    let player = h.spawn_player(Vec3::new(32.0, FLOOR_TOP, 0.0));
+    h.run(frames(0.5, frame_time));
+
+    h.press(KeyCode::W);
+    h.run(1);
+    h.release(KeyCode::W);
+
+    let mut top = FLOOR_TOP;
+    for _ in 0..frames(1.5, frame_time) {
+        h.run(1);
+        top = top.max(h.translation(player).y);
+    }
+    assert_eq!(h.translation(player).y, FLOOR_TOP);
+
+    top - FLOOR_TOP
+}
+
+#[test]
+fn jump_height_does_not_depend_on_the_frame_rate() {
+    let reference = jump_height(1.0 / 60.0);
+    assert!(reference > 60.0, "jumped {}", reference);
+
+    for &hz in [30.0, 144.0].iter() {
+        let height = jump_height(1.0 / hz);
+        assert!(
+            (height - reference).abs() < 1.0,
+            "jumped {} at {} Hz, {} at 60 Hz",
+            height,
+            hz,
+            reference
+        );
+    }
+}
+
+fn attacks_fired(frame_time: f32) -> u32 {
+    let mut h = Harness::new();
+    h.frame_time(frame_time);
+    h.map(FLOOR);
+    h.spawn_player(Vec3::new(32.0, FLOOR_TOP, 0.0));
+    h.run(frames(0.5, frame_time));
+
+    h.press(KeyCode::J);
+    h.run(frames(2.0, frame_time));
+    h.release(KeyCode::J);
+
+    h.attacks_fired()
+}
+
+#[test]
+fn fire_rate_does_not_depend_on_the_frame_rate() {
+    let reference = attacks_fired(1.0 / 60.0);
+    assert!(reference >= 9, "fired {}", reference);
+
+    // at 8 Hz the cooldown runs out between frames
+    for &hz in [8.0, 144.0].iter() {
+        let fired = attacks_fired(1.0 / hz);
+        assert!(
+            (fired as i32 - reference as i32).abs() <= 1,
+            "fired {} at {} Hz, {} at 60 Hz",
+            fired,
+            hz,
+            reference
+        );
+    }
+}
+
+fn attack_defeats(frame_time: f32) -> bool {
+    let mut h = Harness::new();
+    h.frame_time(frame_time);
+    h.map(FLOOR);
+    h.spawn_player(Vec3::new(32.0, FLOOR_TOP, 0.0));
+    h.spawn_enemy(Vec3::new(96.0, FLOOR_TOP, 0.0), 1);
+    h.run(frames(0.5, frame_time));
+
+    h.press(KeyCode::J);
+    h.run(1);
+    h.release(KeyCode::J);
+    h.run(frames(1.0, frame_time));
+
+    h.count::<Enemy>() == 0
+}
+
+#[test]
+fn attack_hits_whatever_the_frame_rate() {
+    // at 8 Hz an attack moves further in a frame than an enemy is wide
+    for &hz in [8.0, 30.0, 144.0].iter() {
+        assert!(attack_defeats(1.0 / hz), "missed at {} Hz", hz);
+    }
+}
+
+#[test]
+fn attack_defeats_an_enemy() {
+    let mut h = Harness::new();
+    h.map(FLOOR);
+    let player = h.spawn_player(Vec3::new(32.0, FLOOR_TOP, 0.0));
+    h.spawn_enemy(Vec3::new(96.0, FLOOR_TOP, 0.0), 1);
+    // the attack is ready once its cooldown has passed
+    h.run(30);
+
+    h.press(KeyCode::J);
+    h.run(1);
+    h.release(KeyCode::J);
+    h.run(1);
+    assert_eq!(h.count::<Attack>(), 1);
+
+    h.run(30);
+    assert_eq!(h.count::<Enemy>(), 0);
+    assert_eq!(h.count::<Attack>(), 0);
+    assert_eq!(h.get(player, |p: &Player| p.life), 30);
+}
+
+#[test]
+fn defeated_enemies_are_told_apart_from_enemies_of_the_same_user() {
+    let mut h = Harness::new();
+    h.map(FLOOR);
+    h.spawn_player(Vec3::new(32.0, FLOOR_TOP, 0.0));
+    let near = h.spawn_enemy(Vec3::new(96.0, FLOOR_TOP, 0.0), 1);
+    let far = h.spawn_enemy(Vec3::new(400.0, FLOOR_TOP, 0.0), 1);
+    assert_eq!(
+        h.get(near, |e: &Enemy| e.user.clone()),
+        h.get(far, |e: &Enemy| e.user.clone())
+    );
+    h.run(30);
+
+    h.press(KeyCode::J);
+    h.run(1);
+    h.release(KeyCode::J);
+    h.run(10);
+
+    assert_eq!(h.count::<Enemy>(), 1);
+    assert_eq!(h.defeated(), vec![h.get(near, |e: &Enemy| e.index)]);
+}
+
+#[test]
+fn attack_hits_one_enemy_only() {
+    let mut h = Harness::new();
+    h.map(FLOOR);
+    h.spawn_player(Vec3::new(32.0, FLOOR_TOP, 0.0));
+    h.spawn_enemy(Vec3::new(96.0, FLOOR_TOP, 0.0), 2);
+    h.spawn_enemy(Vec3::new(96.0, FLOOR_TOP, 0.0), 2);
+    h.run(30);
+
+    h.press(KeyCode::J);
+    h.run(1);
+    h.release(KeyCode::J);
+    h.run(30);
+
+    assert_eq!(h.count::<Attack>(), 0);
+    let life: u32 = h.app.world.query::<&Enemy>().map(|e| e.life).sum();
+    assert_eq!(life, 3);
+}
+
+#[test]
+fn hazard_hurts_the_player() {
+    let mut h = Harness::new();
+    h.map(&["", "", "", "^^^^", "####"]);
+    let player = h.spawn_player(Vec3::new(16.0, 0.0, 0.0));
+
+    h.run(frames(2.0, 1.0 / 60.0));
+
+    let life = h.get(player, |p: &Player| p.life);
+    assert!((26..30).contains(&life), "life {}", life);
+}
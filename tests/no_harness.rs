@@ -0,0 +1,6 @@
+//! Stands in for the gameplay tests when `cargo test` runs without the `harness` feature.
+
+#[cfg(not(feature = "harness"))]
+#[test]
+#[ignore = "the gameplay tests need the harness feature: run `make test`"]
+fn gameplay_tests_need_the_harness_feature() {}
@@ -1,16 +1,92 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::PathBuf;
 use structopt::StructOpt;
-use tiled::{parse_with_path, LayerData};
+use tiled::{parse_with_path, LayerData, Object, ObjectShape, Properties, PropertyValue, Tileset};
 
 #[derive(Serialize, Deserialize)]
-struct Tile {
+struct TileMap {
+    layers: Vec<Layer>,
+    shapes: HashMap<u32, TileShape>,
+    objects: Vec<MapObject>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Layer {
+    name: String,
+    order: usize,
+    visible: bool,
+    opacity: f32,
+    parallax: (f32, f32),
+    solid: bool,
     map: Vec<Vec<u32>>,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ShapeKind {
+    Solid,
+    OneWay,
+    Slope,
+    Hazard,
+    None,
+}
+
+/// Rectangle in tile-local pixels, origin at the bottom-left corner of the tile.
+#[derive(Serialize, Deserialize)]
+struct ShapeRect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+/// Collision shape of a tile. Tiles without an entry collide as a full solid block.
+#[derive(Serialize, Deserialize)]
+struct TileShape {
+    kind: ShapeKind,
+    /// Collision rectangles; empty means the whole tile.
+    rects: Vec<ShapeRect>,
+    /// Surface height at the left and right edges of a slope tile.
+    slope: Option<(f32, f32)>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum EnemyAi {
+    Wander,
+    Patrol,
+    Guard,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ObjectKind {
+    PlayerSpawn,
+    Enemy {
+        ai: EnemyAi,
+        /// Left and right bounds of a patrol in map pixels.
+        patrol: Option<(f32, f32)>,
+        boss: bool,
+    },
+    Checkpoint,
+}
+
+/// Object from an object layer, in map pixels from the top-left corner of the map as in Tiled.
+#[derive(Serialize, Deserialize)]
+struct MapObject {
+    name: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    #[serde(flatten)]
+    kind: ObjectKind,
+}
+
 #[derive(StructOpt)]
 struct Opt {
     /// Tile map
@@ -24,6 +100,120 @@ struct Opt {
     output: PathBuf,
 }
 
+fn bool_property(properties: &Properties, name: &str) -> Option<bool> {
+    match properties.get(name) {
+        Some(PropertyValue::BoolValue(v)) => Some(*v),
+        _ => None,
+    }
+}
+
+fn float_property(properties: &Properties, name: &str) -> Option<f32> {
+    match properties.get(name) {
+        Some(PropertyValue::FloatValue(v)) => Some(*v),
+        Some(PropertyValue::IntValue(v)) => Some(*v as f32),
+        _ => None,
+    }
+}
+
+fn string_property<'a>(properties: &'a Properties, name: &str) -> Option<&'a str> {
+    match properties.get(name) {
+        Some(PropertyValue::StringValue(v)) => Some(v),
+        _ => None,
+    }
+}
+
+fn tile_shapes(tileset: &Tileset) -> Result<HashMap<u32, TileShape>> {
+    let mut shapes = HashMap::new();
+
+    for tile in &tileset.tiles {
+        let kind = match string_property(&tile.properties, "collision") {
+            Some("solid") => Some(ShapeKind::Solid),
+            Some("one_way") => Some(ShapeKind::OneWay),
+            Some("slope") => Some(ShapeKind::Slope),
+            Some("hazard") => Some(ShapeKind::Hazard),
+            Some("none") => Some(ShapeKind::None),
+            Some(v) => anyhow::bail!("unknown collision `{}` on tile {}", v, tile.id),
+            None => None,
+        };
+
+        // Tiled object coordinates are y-down from the top-left corner of the tile.
+        let rects: Vec<_> = tile
+            .objectgroup
+            .iter()
+            .flat_map(|group| group.objects.iter())
+            .filter_map(|object| match object.shape {
+                ObjectShape::Rect { width, height } => Some(ShapeRect {
+                    x: object.x,
+                    y: tileset.tile_height as f32 - object.y - height,
+                    width,
+                    height,
+                }),
+                _ => None,
+            })
+            .collect();
+
+        let kind = match kind {
+            Some(kind) => kind,
+            None if !rects.is_empty() => ShapeKind::Solid,
+            None => continue,
+        };
+
+        let slope = if kind == ShapeKind::Slope {
+            let height = tileset.tile_height as f32;
+            Some((
+                float_property(&tile.properties, "slope_left").unwrap_or(0.0),
+                float_property(&tile.properties, "slope_right").unwrap_or(height),
+            ))
+        } else {
+            None
+        };
+
+        shapes.insert(
+            tileset.first_gid + tile.id,
+            TileShape { kind, rects, slope },
+        );
+    }
+
+    Ok(shapes)
+}
+
+fn map_object(object: &Object) -> Result<Option<MapObject>> {
+    let kind = match object.obj_type.as_str() {
+        "player_spawn" => ObjectKind::PlayerSpawn,
+        "enemy" => {
+            let ai = match string_property(&object.properties, "ai") {
+                Some("wander") | None => EnemyAi::Wander,
+                Some("patrol") => EnemyAi::Patrol,
+                Some("guard") => EnemyAi::Guard,
+                Some(v) => anyhow::bail!("unknown ai `{}` on object {}", v, object.id),
+            };
+            let patrol = match (
+                float_property(&object.properties, "patrol_left"),
+                float_property(&object.properties, "patrol_right"),
+            ) {
+                (Some(left), Some(right)) => Some((left, right)),
+                _ => None,
+            };
+            ObjectKind::Enemy {
+                ai,
+                patrol,
+                boss: bool_property(&object.properties, "boss").unwrap_or(false),
+            }
+        }
+        "checkpoint" => ObjectKind::Checkpoint,
+        _ => return Ok(None),
+    };
+
+    Ok(Some(MapObject {
+        name: object.name.clone(),
+        x: object.x,
+        y: object.y,
+        width: object.width,
+        height: object.height,
+        kind,
+    }))
+}
+
 fn main() -> Result<()> {
     let opt = Opt::from_args();
 
@@ -34,26 +224,76 @@ fn main() -> Result<()> {
     let map = parse_with_path(reader, &opt.tsx)
         .with_context(|| format!("couldn't parse tile map: {}", opt.tmx.display()))?;
 
-    if let Some(map) = map.layers.first() {
-        if let LayerData::Finite(map) = &map.tiles {
-            let tile = Tile {
-                map: map
-                    .iter()
-                    .map(|v| v.iter().map(|v| v.gid).collect())
-                    .collect(),
-            };
-            serde_json::to_writer(
-                File::create(&opt.output).with_context(|| {
-                    format!(
-                        "couldn't write to the output file: {}",
-                        opt.output.display()
-                    )
-                })?,
-                &tile,
-            )
-            .with_context(|| format!("couldn't pack to json: {}", opt.output.display()))?;
+    let mut layers = vec![];
+
+    for (order, layer) in map.layers.iter().enumerate() {
+        let tiles = match &layer.tiles {
+            LayerData::Finite(tiles) => tiles,
+            _ => {
+                eprintln!("skipping infinite layer: {}", layer.name);
+                continue;
+            }
+        };
+
+        // Tiled 1.4 has no parallax attribute yet; take it from custom properties.
+        let parallax = (
+            float_property(&layer.properties, "parallax_x").unwrap_or(1.0),
+            float_property(&layer.properties, "parallax_y").unwrap_or(1.0),
+        );
+
+        layers.push(Layer {
+            name: layer.name.clone(),
+            order,
+            visible: layer.visible,
+            opacity: layer.opacity,
+            parallax,
+            solid: bool_property(&layer.properties, "solid").unwrap_or(false),
+            map: tiles
+                .iter()
+                .map(|v| v.iter().map(|v| v.gid).collect())
+                .collect(),
+        });
+    }
+
+    let mut shapes = HashMap::new();
+    for tileset in &map.tilesets {
+        shapes.extend(
+            tile_shapes(tileset)
+                .with_context(|| format!("couldn't read tileset: {}", tileset.name))?,
+        );
+    }
+
+    let mut objects = vec![];
+    for group in &map.object_groups {
+        for object in &group.objects {
+            match map_object(object)
+                .with_context(|| format!("couldn't read object layer: {}", group.name))?
+            {
+                Some(object) => objects.push(object),
+                None => eprintln!(
+                    "skipping object of unknown type `{}`: {}",
+                    object.obj_type, object.id
+                ),
+            }
         }
     }
 
+    let tilemap = TileMap {
+        layers,
+        shapes,
+        objects,
+    };
+
+    serde_json::to_writer(
+        File::create(&opt.output).with_context(|| {
+            format!(
+                "couldn't write to the output file: {}",
+                opt.output.display()
+            )
+        })?,
+        &tilemap,
+    )
+    .with_context(|| format!("couldn't pack to json: {}", opt.output.display()))?;
+
     Ok(())
 }
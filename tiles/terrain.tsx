@@ -1,4 +1,66 @@
 <?xml version="1.0" encoding="UTF-8"?>
 <tileset version="1.4" tiledversion="1.4.3" name="Tileset" tilewidth="16" tileheight="16" tilecount="88" columns="11">
- <image source="Tiny Platformer - Hills Asset Pack/Tiny Platformer - Hills Asset Pack/Tileset(16x16)/Tileset.png" width="176" height="135"/>
+ <image source="../static/textures/tiles.png" width="176" height="135"/>
+ <tile id="0">
+  <properties>
+   <property name="collision" value="slope"/>
+   <property name="slope_left" type="float" value="0"/>
+   <property name="slope_right" type="float" value="16"/>
+  </properties>
+ </tile>
+ <tile id="1">
+  <properties>
+   <property name="collision" value="slope"/>
+   <property name="slope_left" type="float" value="16"/>
+   <property name="slope_right" type="float" value="0"/>
+  </properties>
+ </tile>
+ <tile id="2">
+  <properties>
+   <property name="collision" value="slope"/>
+   <property name="slope_left" type="float" value="0"/>
+   <property name="slope_right" type="float" value="8"/>
+  </properties>
+ </tile>
+ <tile id="3">
+  <properties>
+   <property name="collision" value="slope"/>
+   <property name="slope_left" type="float" value="8"/>
+   <property name="slope_right" type="float" value="16"/>
+  </properties>
+ </tile>
+ <tile id="4">
+  <properties>
+   <property name="collision" value="slope"/>
+   <property name="slope_left" type="float" value="16"/>
+   <property name="slope_right" type="float" value="8"/>
+  </properties>
+ </tile>
+ <tile id="5">
+  <properties>
+   <property name="collision" value="slope"/>
+   <property name="slope_left" type="float" value="8"/>
+   <property name="slope_right" type="float" value="0"/>
+  </properties>
+ </tile>
+ <tile id="19">
+  <properties>
+   <property name="collision" value="one_way"/>
+  </properties>
+ </tile>
+ <tile id="20">
+  <properties>
+   <property name="collision" value="one_way"/>
+  </properties>
+ </tile>
+ <tile id="30">
+  <properties>
+   <property name="collision" value="none"/>
+  </properties>
+ </tile>
+ <tile id="31">
+  <properties>
+   <property name="collision" value="none"/>
+  </properties>
+ </tile>
 </tileset>